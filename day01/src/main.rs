@@ -1,58 +1,260 @@
 #![allow(unused_doc_comments)]
 
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::env;
-use std::fs;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::num::ParseIntError;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let task = &args[1];
     let filename = &args[2];
 
-    let freq_changes = fs::read_to_string(filename).unwrap();
+    let reader: Box<dyn BufRead> = if filename == "-" {
+        Box::new(BufReader::new(io::stdin()))
+    } else {
+        Box::new(BufReader::new(
+            File::open(filename).expect("Couldn't open file"),
+        ))
+    };
+    let changes = read_changes(reader).expect("Couldn't read frequency changes");
 
     match task.as_str() {
-        "change" => println!("{}", change_freq(&freq_changes)),
-        "calibrate" => println!("{}", calibrate_freq(&freq_changes)),
+        "change" => println!("{}", changes.iter().sum::<i64>()),
+        "report" => {
+            let n_passes = args
+                .get(3)
+                .map_or(10, |n| n.parse().expect("Couldn't parse number of passes"));
+            print!("{}", drift_report(&changes, n_passes));
+        }
+        "calibrate" => match first_repeat(&changes) {
+            Some(freq) => println!("{}", freq),
+            None => println!("No frequency ever repeats"),
+        },
         _ => panic!("Don't know how to '{}'", task),
     }
 }
 
-fn change_freq(changes: &str) -> i64 {
-    /// Given frequency changes, return the final frequency
-    ///
-    /// # Arguments
-    ///
-    /// * `changes` - a whitespace-separated list of ±integer frequency changes
-    changes
-        .split_whitespace()
-        .fold(0, |freq, change| freq + change.parse::<i64>().unwrap())
+#[derive(Debug)]
+#[allow(dead_code)] // only read through Debug
+enum ReadError {
+    Io(io::Error),
+    Int(ParseIntError),
 }
 
-fn calibrate_freq(input: &str) -> i64 {
-    /// Given frequency changes, find the first repeated frequency after applying them repeatedly
-    ///
-    /// # Arguments
+impl From<io::Error> for ReadError {
+    fn from(err: io::Error) -> ReadError {
+        ReadError::Io(err)
+    }
+}
+
+impl From<ParseIntError> for ReadError {
+    fn from(err: ParseIntError) -> ReadError {
+        ReadError::Int(err)
+    }
+}
+
+fn read_changes<R: BufRead>(reader: R) -> Result<Vec<i64>, ReadError> {
+    /// Read whitespace-separated ±integer frequency changes from a stream,
+    /// a line at a time, so the raw input never has to be held in memory
+    let mut changes: Vec<i64> = Vec::new();
+    for line in reader.lines() {
+        for change in line?.split_whitespace() {
+            changes.push(change.parse::<i64>()?);
+        }
+    }
+    Ok(changes)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Repeat {
+    freq: i64,
+    pass: i64,    // 1-based pass during which the repeat happens
+    index: usize, // 0-based index of the change that reaches the repeated frequency
+}
+
+fn first_repeat(changes: &[i64]) -> Option<i64> {
+    find_first_repeat(changes).map(|r| r.freq)
+}
+
+fn find_first_repeat(changes: &[i64]) -> Option<Repeat> {
+    /// Find the first repeated frequency without repeating passes,
+    /// along with when it happens
     ///
-    /// * `changes` - a whitespace-separated list of ±integer frequency changes
-    let mut past_freqs: HashSet<i64> = HashSet::new();
+    /// During pass k, the frequency before change i is `f_i + k * drift`,
+    /// where `f_i` is the frequency before change i on the first pass and
+    /// `drift` is the net change of a whole pass. If nothing repeats within
+    /// the first pass, a later value `f_j + k * drift` can only land on `f_i`
+    /// when `f_i - f_j` is a positive multiple `k` of `drift`. So group the
+    /// first-pass frequencies by their residue mod `drift`; within a group,
+    /// each `f_j` first repeats on its nearest neighbor in the drift direction,
+    /// and the earliest (pass, index) among those wins.
+    let n = changes.len();
+    let mut freqs: Vec<i64> = Vec::with_capacity(n);
+    let mut seen: HashSet<i64> = HashSet::with_capacity(n);
+    let mut freq: i64 = 0;
+    for change in changes {
+        if seen.contains(&freq) {
+            return Some(Repeat {
+                freq,
+                pass: 1,
+                index: freqs.len() - 1,
+            });
+        }
+        seen.insert(freq);
+        freqs.push(freq);
+        freq += change;
+    }
+    let drift = freq;
+    if freqs.is_empty() {
+        return None;
+    }
+    if seen.contains(&freq) {
+        // the first pass ended on a frequency it already visited
+        // (always the case with zero drift, since 0 was visited first)
+        return Some(Repeat {
+            freq,
+            pass: 1,
+            index: n - 1,
+        });
+    }
+
+    let mut groups: HashMap<i64, Vec<(i64, usize)>> = HashMap::new();
+    for (idx, f) in freqs.iter().enumerate() {
+        groups
+            .entry(f.rem_euclid(drift.abs()))
+            .or_default()
+            .push((*f, idx));
+    }
+
+    let mut best: Option<(i64, usize, i64)> = None; // (pass, index, frequency)
+    for group in groups.values_mut() {
+        group.sort();
+        for pair in group.windows(2) {
+            let (lo, hi) = (pair[0], pair[1]);
+            // with positive drift the lower frequency climbs to the higher one
+            let ((from, idx), to) = if drift > 0 { (lo, hi.0) } else { (hi, lo.0) };
+            let pass = (to - from) / drift;
+            if best.is_none_or(|(p, i, _)| (pass, idx) < (p, i)) {
+                best = Some((pass, idx, to));
+            }
+        }
+    }
+    // (pass, index) so far counts passes completed before reaching change `index`
+    best.map(|(pass, idx, freq)| {
+        if idx == 0 {
+            Repeat {
+                freq,
+                pass,
+                index: n - 1,
+            }
+        } else {
+            Repeat {
+                freq,
+                pass: pass + 1,
+                index: idx - 1,
+            }
+        }
+    })
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct DriftReport {
+    n_passes: usize,
+    pass_freqs: Vec<i64>, // frequency at the end of each pass
+    min: i64,
+    max: i64,
+    first_repeat: Option<Repeat>,
+    repeated: Vec<(i64, usize)>, // frequencies hit more than once, with hit counts
+}
 
+fn drift_report(changes: &[i64], n_passes: usize) -> DriftReport {
+    /// Summarize how the frequency drifts over the first `n_passes` passes
+    /// The starting frequency of 0 counts as a hit; the first repeat is
+    /// found analytically, so it is reported even if it's beyond `n_passes`
+    let mut hits: HashMap<i64, usize> = HashMap::new();
+    let mut pass_freqs: Vec<i64> = Vec::with_capacity(n_passes);
     let mut freq: i64 = 0;
+    let mut min = freq;
+    let mut max = freq;
+    *hits.entry(freq).or_insert(0) += 1;
+    for _ in 0..n_passes {
+        for change in changes {
+            freq += change;
+            min = min.min(freq);
+            max = max.max(freq);
+            *hits.entry(freq).or_insert(0) += 1;
+        }
+        pass_freqs.push(freq);
+    }
+    let mut repeated: Vec<(i64, usize)> = hits.into_iter().filter(|&(_, n)| n > 1).collect();
+    repeated.sort();
+    DriftReport {
+        n_passes,
+        pass_freqs,
+        min,
+        max,
+        first_repeat: find_first_repeat(changes),
+        repeated,
+    }
+}
 
-    for change in input.split_whitespace().cycle() {
-        past_freqs.insert(freq);
-        freq += change.parse::<i64>().unwrap();
-        if past_freqs.contains(&freq) {
-            break;
+impl fmt::Display for DriftReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, freq) in self.pass_freqs.iter().enumerate() {
+            writeln!(f, "after pass {}: {}", i + 1, freq)?;
+        }
+        writeln!(
+            f,
+            "range over {} passes: {} to {}",
+            self.n_passes, self.min, self.max
+        )?;
+        match self.first_repeat {
+            Some(r) => writeln!(
+                f,
+                "first repeat: {} on pass {}, change {}",
+                r.freq, r.pass, r.index
+            )?,
+            None => writeln!(f, "first repeat: never")?,
         }
+        writeln!(f, "{} frequencies hit more than once:", self.repeated.len())?;
+        for (freq, n) in &self.repeated {
+            writeln!(f, "  {} ({} times)", freq, n)?;
+        }
+        Ok(())
     }
-    freq
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn changes(input: &str) -> Vec<i64> {
+        read_changes(input.as_bytes()).unwrap()
+    }
+
+    fn repeat_by_passes(changes: &[i64]) -> i64 {
+        /// Reference implementation that actually repeats the passes
+        /// (loops forever if no frequency repeats)
+        let mut past_freqs: HashSet<i64> = HashSet::new();
+
+        let mut freq: i64 = 0;
+
+        for change in changes.iter().cycle() {
+            past_freqs.insert(freq);
+            freq += change;
+            if past_freqs.contains(&freq) {
+                break;
+            }
+        }
+        freq
+    }
+
     #[test]
     fn test_prob1_examples() {
         struct TestCase {
@@ -80,7 +282,7 @@ mod tests {
         ];
 
         for ref case in &cases[..] {
-            assert_eq!(case.output, change_freq(&case.input))
+            assert_eq!(case.output, changes(&case.input).iter().sum::<i64>())
         }
     }
 
@@ -111,7 +313,138 @@ mod tests {
         ];
 
         for ref case in &cases[..] {
-            assert_eq!(case.output, calibrate_freq(&case.input))
+            assert_eq!(Some(case.output), first_repeat(&changes(&case.input)));
+            assert_eq!(case.output, repeat_by_passes(&changes(&case.input)));
+        }
+    }
+
+    #[test]
+    fn test_calibrate_matches_passes() {
+        struct TestCase {
+            input: &'static str,
+            output: i64,
+        }
+
+        // repeating on passes 2 to 5
+        let cases: Vec<TestCase> = vec![
+            TestCase {
+                input: "+2 +8 -9",
+                output: 2,
+            },
+            TestCase {
+                input: "-3 -8 +10",
+                output: -3,
+            },
+            TestCase {
+                input: "+4 -9 +6",
+                output: 4,
+            },
+            TestCase {
+                input: "+3 +5 -6",
+                output: 8,
+            },
+            TestCase {
+                input: "-7 +3 +3",
+                output: -7,
+            },
+            TestCase {
+                input: "+9 -4 -4",
+                output: 9,
+            },
+        ];
+
+        for case in &cases[..] {
+            assert_eq!(Some(case.output), first_repeat(&changes(case.input)));
+            assert_eq!(case.output, repeat_by_passes(&changes(case.input)));
+        }
+    }
+
+    #[test]
+    fn test_no_repeat() {
+        assert_eq!(None, first_repeat(&changes("+1\n+1\n")));
+        assert_eq!(None, first_repeat(&changes("+3\n-1\n")));
+        assert_eq!(None, first_repeat(&changes("")));
+        assert_eq!(None, first_repeat(&changes("+2\n-1\n+2\n")));
+        assert_eq!(Some(3), first_repeat(&changes("+3\n-1\n-1\n")));
+    }
+
+    #[test]
+    fn test_huge_drift() {
+        // repeats only after 10^15 passes
+        let input = "+1000000000000000\n-999999999999999\n";
+        assert_eq!(Some(1_000_000_000_000_000), first_repeat(&changes(input)));
+        let input = "-1000000000000000\n+999999999999999\n";
+        assert_eq!(Some(-1_000_000_000_000_000), first_repeat(&changes(input)));
+    }
+
+    #[test]
+    fn test_find_first_repeat() {
+        let at = |input: &str| find_first_repeat(&changes(input));
+        // 0, 1, 0
+        assert_eq!(
+            Some(Repeat {
+                freq: 0,
+                pass: 1,
+                index: 1
+            }),
+            at("+1 -1")
+        );
+        // 0, 3, 6, 10, 8, 4, 7, 10
+        assert_eq!(
+            Some(Repeat {
+                freq: 10,
+                pass: 2,
+                index: 1
+            }),
+            at("+3 +3 +4 -2 -4")
+        );
+        // 0, 7, 14, 12, 5, 1, 8, 15, 13, 6, 2, 9, 16, 14
+        assert_eq!(
+            Some(Repeat {
+                freq: 14,
+                pass: 3,
+                index: 2
+            }),
+            at("+7 +7 -2 -7 -4")
+        );
+        // 0, 4, 3, 2, 6, 5, 4: repeats on the last change of a pass
+        assert_eq!(
+            Some(Repeat {
+                freq: 4,
+                pass: 2,
+                index: 2
+            }),
+            at("+4 -1 -1")
+        );
+        assert_eq!(None, at("+1"));
+    }
+
+    #[test]
+    fn test_drift_report() {
+        let changes = vec![3, 3, 4, -2, -4];
+        let report = drift_report(&changes, 2);
+        assert_eq!(vec![4, 8], report.pass_freqs);
+        assert_eq!(0, report.min);
+        assert_eq!(14, report.max);
+        assert_eq!(
+            Some(Repeat {
+                freq: 10,
+                pass: 2,
+                index: 1
+            }),
+            report.first_repeat
+        );
+        // 0, 3, 6, 10, 8, 4, 7, 10, 14, 12, 8
+        assert_eq!(vec![(8, 2), (10, 2)], report.repeated);
+    }
+
+    #[test]
+    fn test_read_changes() {
+        let input = "+1\n-2 +3\n\n+1\n";
+        assert_eq!(vec![1, -2, 3, 1], read_changes(input.as_bytes()).unwrap());
+        match read_changes("+1\nbogus\n".as_bytes()) {
+            Err(ReadError::Int(_)) => (),
+            r => panic!("expected a parse error, got {:?}", r),
         }
     }
 }
@@ -1,8 +1,13 @@
 #![allow(unused_doc_comments)]
 
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::env;
 use std::fs;
+use std::hash::{Hash, Hasher};
 use std::iter::FromIterator;
 
 fn main() {
@@ -17,12 +22,55 @@ fn main() {
             .map(|s| s.to_owned()),
     );
     match task.as_str() {
-        "checksum" => println!("{}", checksum(&ids)),
+        "checksum" => println!("{}", checksum_with(&ids, &multiplicities_arg(&args))),
+        "checksumreport" => {
+            let multiplicities = multiplicities_arg(&args);
+            let buckets = checksum_buckets(&ids, &multiplicities);
+            for (multiplicity, contributors) in &buckets {
+                println!("{} ({} ids):", multiplicity, contributors.len());
+                for i in contributors {
+                    println!("  {}", ids[*i]);
+                }
+            }
+            println!("checksum: {}", checksum_with(&ids, &multiplicities));
+        }
         "common" => println!("{}", common_for_similar_ids(&ids)),
+        "similar" => {
+            let metric = match args.get(3).map_or("hamming", |m| m.as_str()) {
+                "hamming" => Metric::Hamming,
+                "levenshtein" => Metric::Levenshtein,
+                m => panic!("Don't know the metric '{}'", m),
+            };
+            let k: usize = args
+                .get(4)
+                .map_or(1, |k| k.parse().expect("Couldn't parse distance"));
+            for (i, j, d) in similar_pairs(&ids, metric, k) {
+                println!("{} {} {}", ids[i], ids[j], d);
+            }
+        }
         _ => panic!("Don't know how to '{}'", task),
     }
 }
 
+fn multiplicities_arg(args: &[String]) -> Vec<u64> {
+    /// Letter multiplicities for the checksum, from an optional comma-separated 4th argument
+    /// No letter appears 0 times, and each multiplicity only counts once
+    let multiplicities: Vec<u64> = args.get(3).map_or(vec![2, 3], |m| {
+        m.split(',')
+            .map(|n| n.parse().expect("Couldn't parse multiplicity"))
+            .collect()
+    });
+    for (i, m) in multiplicities.iter().enumerate() {
+        if *m == 0 {
+            panic!("A multiplicity of 0 never counts a letter");
+        }
+        if multiplicities[..i].contains(m) {
+            panic!("Multiplicity {} is given more than once", m);
+        }
+    }
+    multiplicities
+}
+
 fn count_letters(id: &str) -> HashMap<char, u64> {
     /// Given an id, return a map of letter to number of occurrences in the id
     let mut counts: HashMap<char, u64> = HashMap::new();
@@ -35,59 +83,189 @@ fn count_letters(id: &str) -> HashMap<char, u64> {
 
 #[derive(Debug, PartialEq, Eq)]
 struct ChecksumItem {
-    contains_2: bool,
-    contains_3: bool,
+    contains: BTreeSet<u64>, // which of the wanted multiplicities some letter appears with
 }
 
-fn id_to_checksum_item(id: &str) -> ChecksumItem {
+fn id_to_checksum_item(id: &str, multiplicities: &[u64]) -> ChecksumItem {
     /// Given an id, return a struct of the information needed to compute the checksum
-    let mut item: ChecksumItem = ChecksumItem {
-        contains_2: false,
-        contains_3: false,
-    };
-    for count in count_letters(id).values() {
-        match count {
-            2 => item.contains_2 = true,
-            3 => item.contains_3 = true,
-            _ => (),
-        }
+    /// Letters are any Unicode characters, counted as-is (no case folding)
+    ChecksumItem {
+        contains: count_letters(id)
+            .values()
+            .filter(|count| multiplicities.contains(count))
+            .cloned()
+            .collect(),
     }
-    item
 }
 
-fn checksum(ids: &[String]) -> u64 {
-    /// Given ids, return the checksum of the set
-    let (n_2, n_3) = ids
-        .iter()
-        .map(|id| id_to_checksum_item(id))
-        .fold((0, 0), |(n_2, n_3), item| {
-            (n_2 + item.contains_2 as u64, n_3 + item.contains_3 as u64)
-        });
-    n_2 * n_3
+fn checksum_buckets(ids: &[String], multiplicities: &[u64]) -> BTreeMap<u64, Vec<usize>> {
+    /// For each wanted multiplicity, the indices of the ids containing
+    /// some letter exactly that many times
+    let mut buckets: BTreeMap<u64, Vec<usize>> =
+        multiplicities.iter().map(|&m| (m, Vec::new())).collect();
+    for (i, id) in ids.iter().enumerate() {
+        for m in id_to_checksum_item(id, multiplicities).contains {
+            buckets.get_mut(&m).unwrap().push(i);
+        }
+    }
+    buckets
 }
 
-fn are_similar(s1: &str, s2: &str) -> bool {
-    /// return if the two strings are similar (differing in at most 1 place)
-    let n_dif = Iterator::zip(s1.chars(), s2.chars())
-        .filter(|(c1, c2)| c1 != c2)
-        .count();
-    n_dif <= 1
+fn checksum_with(ids: &[String], multiplicities: &[u64]) -> u64 {
+    /// Given ids, return the product of the number of ids in each multiplicity's bucket
+    checksum_buckets(ids, multiplicities)
+        .values()
+        .map(|contributors| contributors.len() as u64)
+        .product()
 }
 
 fn common_for_similar_ids(ids: &[String]) -> String {
     /// return the common characters between two similar ids
-    for i in 1..ids.len() {
-        let s1 = &ids[i - 1];
-        for s2 in ids[i..].iter() {
-            if are_similar(&s1, &s2) {
-                return Iterator::zip(s1.chars(), s2.chars())
-                    .filter(|(c1, c2)| c1 == c2)
-                    .map(|(c1, _c2)| c1)
-                    .collect::<String>();
+    match similar_pairs(ids, Metric::Hamming, 1).first() {
+        Some(&(i, j, _)) => Iterator::zip(ids[i].chars(), ids[j].chars())
+            .filter(|(c1, c2)| c1 == c2)
+            .map(|(c1, _c2)| c1)
+            .collect::<String>(),
+        None => panic!("No similar ids!"),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Metric {
+    Hamming,     // substitutions only; ids of different lengths are never similar
+    Levenshtein, // insertions, deletions, and substitutions
+}
+
+fn hamming(s1: &[char], s2: &[char]) -> Option<usize> {
+    /// number of positions where two equal-length ids differ
+    if s1.len() != s2.len() {
+        return None;
+    }
+    Some(s1.iter().zip(s2.iter()).filter(|(c1, c2)| c1 != c2).count())
+}
+
+fn levenshtein(s1: &[char], s2: &[char]) -> usize {
+    /// edit distance between two ids, keeping just one row of the table
+    let mut row: Vec<usize> = (0..=s2.len()).collect();
+    for (i, c1) in s1.iter().enumerate() {
+        let mut diag = row[0];
+        row[0] = i + 1;
+        for (j, c2) in s2.iter().enumerate() {
+            let substitute = diag + if c1 == c2 { 0 } else { 1 };
+            diag = row[j + 1];
+            row[j + 1] = substitute.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[s2.len()]
+}
+
+fn similar_pairs(ids: &[String], metric: Metric, k: usize) -> Vec<(usize, usize, usize)> {
+    /// Find every pair of ids within distance k of each other
+    /// Returns (i, j, distance) with i < j, sorted
+    let chars: Vec<Vec<char>> = ids.iter().map(|id| id.chars().collect()).collect();
+    let mut pairs = match metric {
+        Metric::Hamming => hamming_pairs(&chars, k),
+        Metric::Levenshtein => levenshtein_pairs(&chars, k),
+    };
+    pairs.sort();
+    pairs
+}
+
+fn hamming_pairs(ids: &[Vec<char>], k: usize) -> Vec<(usize, usize, usize)> {
+    /// Split each id into k + 1 segments. Two ids within k substitutions
+    /// of each other must match exactly on at least one segment, so only
+    /// ids sharing a (length, segment, contents) bucket need comparing.
+    let mut buckets: HashMap<(usize, usize, &[char]), Vec<usize>> = HashMap::new();
+    for (i, id) in ids.iter().enumerate() {
+        let n = id.len();
+        for seg in 0..=k {
+            let lo = seg * n / (k + 1);
+            let hi = (seg + 1) * n / (k + 1);
+            buckets.entry((n, seg, &id[lo..hi])).or_default().push(i);
+        }
+    }
+    let mut seen: HashSet<(usize, usize)> = HashSet::new();
+    let mut pairs: Vec<(usize, usize, usize)> = Vec::new();
+    for bucket in buckets.values() {
+        for (a, &i) in bucket.iter().enumerate() {
+            for &j in &bucket[a + 1..] {
+                if !seen.insert((i, j)) {
+                    continue;
+                }
+                match hamming(&ids[i], &ids[j]) {
+                    Some(d) if d <= k => pairs.push((i, j, d)),
+                    _ => (),
+                }
             }
         }
     }
-    panic!("No similar ids!");
+    pairs
+}
+
+fn deletion_variants(id: &[char], k: usize) -> Vec<u64> {
+    /// Hashes of every string reachable by deleting up to k characters
+    let mut variants: HashSet<Vec<char>> = HashSet::new();
+    let mut frontier: Vec<Vec<char>> = vec![id.to_vec()];
+    variants.insert(id.to_vec());
+    for _ in 0..k {
+        let mut next: Vec<Vec<char>> = Vec::new();
+        for v in &frontier {
+            for i in 0..v.len() {
+                let mut shorter = v.clone();
+                shorter.remove(i);
+                if variants.insert(shorter.clone()) {
+                    next.push(shorter);
+                }
+            }
+        }
+        frontier = next;
+    }
+    variants
+        .iter()
+        .map(|v| {
+            let mut hasher = DefaultHasher::new();
+            v.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect()
+}
+
+fn levenshtein_pairs(ids: &[Vec<char>], k: usize) -> Vec<(usize, usize, usize)> {
+    /// Two ids within edit distance k share a string reachable from both
+    /// by deleting at most k characters (drop the substituted, inserted, and
+    /// deleted characters), so bucket ids by the hashes of their deletion
+    /// variants and only compare ids that share a bucket.
+    /// Hash collisions just add candidates, which the real distance weeds out.
+    let mut entries: Vec<(u64, usize)> = ids
+        .iter()
+        .enumerate()
+        .flat_map(|(i, id)| deletion_variants(id, k).into_iter().map(move |h| (h, i)))
+        .collect();
+    entries.sort_unstable();
+
+    let mut seen: HashSet<(usize, usize)> = HashSet::new();
+    let mut pairs: Vec<(usize, usize, usize)> = Vec::new();
+    let mut lo = 0;
+    while lo < entries.len() {
+        let mut hi = lo + 1;
+        while hi < entries.len() && entries[hi].0 == entries[lo].0 {
+            hi += 1;
+        }
+        for a in lo..hi {
+            for b in (a + 1)..hi {
+                let (i, j) = (entries[a].1, entries[b].1);
+                if i == j || !seen.insert((i, j)) {
+                    continue;
+                }
+                let d = levenshtein(&ids[i], &ids[j]);
+                if d <= k {
+                    pairs.push((i, j, d));
+                }
+            }
+        }
+        lo = hi;
+    }
+    pairs
 }
 
 #[cfg(test)]
@@ -119,6 +297,12 @@ mod tests {
 
     #[test]
     fn test_id_to_checksum_item() {
+        fn item(contains: &[u64]) -> ChecksumItem {
+            ChecksumItem {
+                contains: contains.iter().cloned().collect(),
+            }
+        }
+
         struct TestCase {
             output: ChecksumItem,
             input: String,
@@ -127,57 +311,36 @@ mod tests {
         let cases: Vec<TestCase> = vec![
             TestCase {
                 input: String::from("abcdef"),
-                output: ChecksumItem {
-                    contains_2: false,
-                    contains_3: false,
-                },
+                output: item(&[]),
             },
             TestCase {
                 input: String::from("bababc"),
-                output: ChecksumItem {
-                    contains_2: true,
-                    contains_3: true,
-                },
+                output: item(&[2, 3]),
             },
             TestCase {
                 input: String::from("abbcde"),
-                output: ChecksumItem {
-                    contains_2: true,
-                    contains_3: false,
-                },
+                output: item(&[2]),
             },
             TestCase {
                 input: String::from("abcccd"),
-                output: ChecksumItem {
-                    contains_2: false,
-                    contains_3: true,
-                },
+                output: item(&[3]),
             },
             TestCase {
                 input: String::from("aabcdd"),
-                output: ChecksumItem {
-                    contains_2: true,
-                    contains_3: false,
-                },
+                output: item(&[2]),
             },
             TestCase {
                 input: String::from("abcdee"),
-                output: ChecksumItem {
-                    contains_2: true,
-                    contains_3: false,
-                },
+                output: item(&[2]),
             },
             TestCase {
                 input: String::from("ababab"),
-                output: ChecksumItem {
-                    contains_2: false,
-                    contains_3: true,
-                },
+                output: item(&[3]),
             },
         ];
 
         for ref case in &cases[..] {
-            assert_eq!(case.output, id_to_checksum_item(&case.input))
+            assert_eq!(case.output, id_to_checksum_item(&case.input, &[2, 3]))
         }
     }
 
@@ -202,7 +365,7 @@ mod tests {
         }];
 
         for ref case in &cases[..] {
-            assert_eq!(case.output, checksum(&case.input))
+            assert_eq!(case.output, checksum_with(&case.input, &[2, 3]))
         }
     }
 
@@ -228,7 +391,83 @@ mod tests {
         ];
 
         for ref case in &cases[..] {
-            assert_eq!(case.output, are_similar(&case.input1, &case.input2))
+            let s1: Vec<char> = case.input1.chars().collect();
+            let s2: Vec<char> = case.input2.chars().collect();
+            assert_eq!(case.output, hamming(&s1, &s2).unwrap() <= 1)
+        }
+    }
+
+    #[test]
+    fn test_checksum_buckets() {
+        let ids: Vec<String> = ["bababc", "abcccd", "\u{e9}t\u{e9}", "ÄäÄÄ", "aaaab"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let buckets = checksum_buckets(&ids, &[1, 2, 3, 4]);
+        assert_eq!(vec![0, 1, 2, 3, 4], buckets[&1]);
+        assert_eq!(vec![0, 2], buckets[&2]);
+        assert_eq!(vec![0, 1, 3], buckets[&3]);
+        assert_eq!(vec![4], buckets[&4]);
+        assert_eq!(5 * 2 * 3, checksum_with(&ids, &[1, 2, 3, 4]));
+        assert_eq!(0, checksum_with(&ids, &[5, 2]));
+        assert_eq!(1, checksum_with(&ids, &[]));
+    }
+
+    #[test]
+    fn test_multiplicities_arg() {
+        let args = |extra: &[&str]| -> Vec<String> {
+            ["day02", "checksum", "input.txt"]
+                .iter()
+                .chain(extra)
+                .map(|s| s.to_string())
+                .collect()
+        };
+        assert_eq!(vec![2, 3], multiplicities_arg(&args(&[])));
+        assert_eq!(vec![4, 1, 2], multiplicities_arg(&args(&["4,1,2"])));
+    }
+
+    #[test]
+    fn test_distances() {
+        let chars = |s: &str| s.chars().collect::<Vec<char>>();
+        assert_eq!(Some(2), hamming(&chars("abcde"), &chars("axcye")));
+        assert_eq!(None, hamming(&chars("abcde"), &chars("abcd")));
+        assert_eq!(3, levenshtein(&chars("kitten"), &chars("sitting")));
+        assert_eq!(1, levenshtein(&chars("abcde"), &chars("abde")));
+        assert_eq!(4, levenshtein(&chars(""), &chars("ab\u{e9}c")));
+        assert_eq!(1, levenshtein(&chars("caf\u{e9}"), &chars("cafe")));
+    }
+
+    #[test]
+    fn test_similar_pairs_match_brute_force() {
+        let ids: Vec<String> = vec![
+            "abcde", "fghij", "klmno", "pqrst", "fguij", "axcye", "wvxyz", "abcd", "abde", "fghij",
+            "bcdea", "axcy", "", "a",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let chars: Vec<Vec<char>> = ids.iter().map(|id| id.chars().collect()).collect();
+        for k in 0..4 {
+            let mut hamming_expected: Vec<(usize, usize, usize)> = Vec::new();
+            let mut levenshtein_expected: Vec<(usize, usize, usize)> = Vec::new();
+            for i in 0..ids.len() {
+                for j in (i + 1)..ids.len() {
+                    if let Some(d) = hamming(&chars[i], &chars[j]) {
+                        if d <= k {
+                            hamming_expected.push((i, j, d));
+                        }
+                    }
+                    let d = levenshtein(&chars[i], &chars[j]);
+                    if d <= k {
+                        levenshtein_expected.push((i, j, d));
+                    }
+                }
+            }
+            assert_eq!(hamming_expected, similar_pairs(&ids, Metric::Hamming, k));
+            assert_eq!(
+                levenshtein_expected,
+                similar_pairs(&ids, Metric::Levenshtein, k)
+            );
         }
     }
 
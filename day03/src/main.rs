@@ -1,5 +1,8 @@
 #![allow(unused_doc_comments)]
 
+use std::collections::btree_set;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::env;
 use std::fs;
 use std::num::ParseIntError;
@@ -7,10 +10,6 @@ use std::result::Result;
 use std::str;
 use std::str::FromStr;
 
-const WIDTH: usize = 1000;
-const HEIGHT: usize = 1000;
-const SIZE: usize = WIDTH * HEIGHT;
-
 fn main() {
     let args: Vec<String> = env::args().collect();
     let task = &args[1];
@@ -28,6 +27,39 @@ fn main() {
         for id in find_intact(&claims) {
             println!("{}", id);
         }
+    } else if task == "conflicts" {
+        let graph = ConflictGraph::new(&claims);
+        for (id, conflicts) in graph.ids.iter().zip(graph.conflicts()) {
+            let others: Vec<String> = conflicts
+                .iter()
+                .map(|(other, area)| format!("{}:{}", other, area))
+                .collect();
+            println!("{} {}", id, others.join(" "));
+        }
+    } else if task == "edges" {
+        print!("{}", ConflictGraph::new(&claims).to_edge_list());
+    } else if task == "dot" {
+        print!("{}", ConflictGraph::new(&claims).to_dot());
+    } else if task == "cover" {
+        let x: i64 = args
+            .get(3)
+            .expect("Need an x coordinate")
+            .parse()
+            .expect("Couldn't parse x");
+        let y: i64 = args
+            .get(4)
+            .expect("Need a y coordinate")
+            .parse()
+            .expect("Couldn't parse y");
+        for id in claims_covering(&claims, x, y) {
+            println!("{}", id);
+        }
+    } else if task == "cluster" {
+        let cluster = ConflictGraph::new(&claims).largest_cluster();
+        println!("{} claims", cluster.len());
+        for id in cluster {
+            println!("{}", id);
+        }
     } else {
         panic!("Don't know how to '{}'", task);
     }
@@ -36,10 +68,48 @@ fn main() {
 #[derive(Debug, PartialEq, Eq)]
 struct Claim {
     id: u64,
-    x: usize, // distance between left edge of fabric and edge of claim
-    y: usize, // distance between top edge of fabric and edge of claim
-    width: usize,
-    height: usize,
+    x: i64, // distance between left edge of fabric and edge of claim
+    y: i64, // distance between top edge of fabric and edge of claim
+    width: u64,
+    height: u64,
+}
+
+impl Claim {
+    // edges are widened so that x + width can't overflow
+    fn left(&self) -> i128 {
+        self.x as i128
+    }
+
+    fn right(&self) -> i128 {
+        self.x as i128 + self.width as i128
+    }
+
+    fn top(&self) -> i128 {
+        self.y as i128
+    }
+
+    fn bottom(&self) -> i128 {
+        self.y as i128 + self.height as i128
+    }
+
+    fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    fn covers(&self, x: i64, y: i64) -> bool {
+        let (x, y) = (x as i128, y as i128);
+        self.left() <= x && x < self.right() && self.top() <= y && y < self.bottom()
+    }
+
+    fn shared_area(&self, other: &Claim) -> u128 {
+        let width = self.right().min(other.right()) - self.left().max(other.left());
+        let height = self.bottom().min(other.bottom()) - self.top().max(other.top());
+        if width > 0 && height > 0 {
+            width as u128 * height as u128
+        } else {
+            0
+        }
+    }
 }
 
 impl FromStr for Claim {
@@ -51,18 +121,18 @@ impl FromStr for Claim {
         // parsing to avoid having to return Errors manually
         let fields: Vec<&str> = s.split_whitespace().collect();
 
-        let raw_id = fields.get(0).unwrap_or(&"");
-        let id = raw_id.trim_left_matches('#').parse::<u64>()?;
+        let raw_id = fields.first().unwrap_or(&"");
+        let id = raw_id.trim_start_matches('#').parse::<u64>()?;
 
         let raw_edges = fields.get(2).unwrap_or(&"");
         let edges: Vec<&str> = raw_edges.trim_end_matches(':').split(',').collect();
-        let x = edges.get(0).unwrap_or(&"").parse::<usize>()?;
-        let y = edges.get(1).unwrap_or(&"").parse::<usize>()?;
+        let x = edges.first().unwrap_or(&"").parse::<i64>()?;
+        let y = edges.get(1).unwrap_or(&"").parse::<i64>()?;
 
         let raw_size = fields.get(3).unwrap_or(&"");
         let size: Vec<&str> = raw_size.split('x').collect();
-        let width = size.get(0).unwrap_or(&"").parse::<usize>()?;
-        let height = size.get(1).unwrap_or(&"").parse::<usize>()?;
+        let width = size.first().unwrap_or(&"").parse::<u64>()?;
+        let height = size.get(1).unwrap_or(&"").parse::<u64>()?;
 
         Ok(Self {
             id,
@@ -74,44 +144,432 @@ impl FromStr for Claim {
     }
 }
 
-fn count_claims(claims: &[Claim]) -> Vec<u32> {
-    /// Given claims, return an array of how many claims claim a given square
-    let mut n_claims: Vec<u32> = vec![0; SIZE];
-    for claim in claims {
-        for x in claim.x..(claim.x + claim.width) {
-            for y in claim.y..(claim.y + claim.height) {
-                let idx = WIDTH * y + x;
-                n_claims[idx] += 1
-            }
+/// Coverage of the y axis by the claims crossing the sweep line
+///
+/// A segment tree over the compressed y coordinates. Each node counts the
+/// claims covering its whole span (claims are only ever removed exactly as
+/// they were added, so counts never need pushing down), and from that keeps
+/// the length of its span covered at least once and at least twice.
+struct Coverage {
+    ys: Vec<i128>,
+    count: Vec<u32>,
+    once: Vec<u128>,
+    twice: Vec<u128>,
+}
+
+impl Coverage {
+    fn new(mut ys: Vec<i128>) -> Coverage {
+        ys.sort();
+        ys.dedup();
+        let n_nodes = 4 * ys.len().max(1);
+        Coverage {
+            ys,
+            count: vec![0; n_nodes],
+            once: vec![0; n_nodes],
+            twice: vec![0; n_nodes],
+        }
+    }
+
+    fn covered_twice(&self) -> u128 {
+        self.twice[1]
+    }
+
+    fn update(&mut self, y_lo: i128, y_hi: i128, delta: i32) {
+        /// Add (or with a negative delta, remove) a claim's span [y_lo, y_hi)
+        let lo = self.ys.binary_search(&y_lo).unwrap();
+        let hi = self.ys.binary_search(&y_hi).unwrap();
+        if lo < hi {
+            let n = self.ys.len() - 1;
+            self.update_node(1, 0, n, lo, hi, delta);
         }
     }
-    n_claims
+
+    fn update_node(
+        &mut self,
+        node: usize,
+        n_lo: usize,
+        n_hi: usize,
+        lo: usize,
+        hi: usize,
+        delta: i32,
+    ) {
+        /// node covers elementary intervals [n_lo, n_hi), i.e. ys[n_lo]..ys[n_hi]
+        if hi <= n_lo || n_hi <= lo {
+            return;
+        }
+        if lo <= n_lo && n_hi <= hi {
+            self.count[node] = (self.count[node] as i32 + delta) as u32;
+        } else {
+            let mid = (n_lo + n_hi) / 2;
+            self.update_node(2 * node, n_lo, mid, lo, hi, delta);
+            self.update_node(2 * node + 1, mid, n_hi, lo, hi, delta);
+        }
+        let full = (self.ys[n_hi] - self.ys[n_lo]) as u128;
+        let leaf = n_hi - n_lo == 1;
+        let (l, r) = (2 * node, 2 * node + 1);
+        let (once, twice) = match self.count[node] {
+            0 if leaf => (0, 0),
+            0 => (self.once[l] + self.once[r], self.twice[l] + self.twice[r]),
+            1 if leaf => (full, 0),
+            1 => (full, self.once[l] + self.once[r]),
+            _ => (full, full),
+        };
+        self.once[node] = once;
+        self.twice[node] = twice;
+    }
 }
 
-fn count_overlapping(claims: &[Claim]) -> usize {
+fn count_overlapping(claims: &[Claim]) -> u128 {
     /// Given claims, figure out how much overlapping area there is
-    count_claims(claims).iter().filter(|&&n| n > 1).count()
+    /// Sweeps a vertical line across the claims' left and right edges,
+    /// adding up the doubly-covered length times the distance swept
+    let mut events: Vec<(i128, i32, i128, i128)> = Vec::with_capacity(2 * claims.len());
+    let mut ys: Vec<i128> = Vec::with_capacity(2 * claims.len());
+    for claim in claims.iter().filter(|c| !c.is_empty()) {
+        events.push((claim.left(), 1, claim.top(), claim.bottom()));
+        events.push((claim.right(), -1, claim.top(), claim.bottom()));
+        ys.push(claim.top());
+        ys.push(claim.bottom());
+    }
+    events.sort();
+
+    let mut coverage = Coverage::new(ys);
+    let mut area: u128 = 0;
+    let mut last_x: Option<i128> = None;
+    for (x, delta, y_lo, y_hi) in events {
+        if let Some(last_x) = last_x {
+            area += coverage.covered_twice() * (x - last_x) as u128;
+        }
+        coverage.update(y_lo, y_hi, delta);
+        last_x = Some(x);
+    }
+    area
 }
 
-fn find_intact(claims: &[Claim]) -> Vec<u64> {
-    /// Given claims, return IDs that are intact
-    let mut intact: Vec<u64> = Vec::new();
-    let n_claims = count_claims(claims);
-    for claim in claims {
-        let mut is_intact = true;
-        for x in claim.x..(claim.x + claim.width) {
-            for y in claim.y..(claim.y + claim.height) {
-                let idx = WIDTH * y + x;
-                if n_claims[idx] != 1 {
-                    is_intact = false;
-                }
+fn sweep_events(claims: &[Claim]) -> (Vec<(i128, bool, usize)>, Vec<i128>) {
+    /// The non-empty claims' left and right edges in sweep order, with
+    /// whether each one starts a claim, and their top and bottom edges
+    let live = || claims.iter().enumerate().filter(|(_, c)| !c.is_empty());
+    let mut events: Vec<(i128, bool, usize)> = Vec::with_capacity(2 * claims.len());
+    for (i, claim) in live() {
+        // false sorts first: claims that end here never overlap claims starting here
+        events.push((claim.right(), false, i));
+        events.push((claim.left(), true, i));
+    }
+    events.sort();
+
+    let mut ys: Vec<i128> = live()
+        .flat_map(|(_, claim)| vec![claim.top(), claim.bottom()])
+        .collect();
+    ys.sort();
+    ys.dedup();
+    (events, ys)
+}
+
+fn overlapping_pairs(claims: &[Claim]) -> Vec<(usize, usize)> {
+    /// Given claims, find every pair (by index, smaller first) sharing any area
+    /// Sweeps across the claims' left and right edges, checking each claim
+    /// as it starts against the ones crossing the sweep line
+    let (events, ys) = sweep_events(claims);
+    let mut active = ActiveClaims::new(claims, ys);
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    for (_, starts, i) in events {
+        if !starts {
+            active.remove(i);
+            continue;
+        }
+        for j in active.overlapping(i) {
+            pairs.push((i.min(j), i.max(j)));
+        }
+        active.insert(i);
+    }
+    pairs.sort();
+    pairs
+}
+
+fn conflicted(claims: &[Claim]) -> Vec<bool> {
+    /// Given claims, find which of them share any area with another
+    /// The same sweep as overlapping_pairs, but a claim that's found to
+    /// conflict leaves the claims still to be checked, so however many
+    /// claims overlap, each one is only found once
+    let (events, ys) = sweep_events(claims);
+    let mut active = ActiveClaims::new(claims, ys.clone());
+    let mut unmarked = ActiveClaims::new(claims, ys);
+    let mut is_conflicted: Vec<bool> = vec![false; claims.len()];
+    for (_, starts, i) in events {
+        if !starts {
+            active.remove(i);
+            if !is_conflicted[i] {
+                unmarked.remove(i);
             }
+            continue;
         }
-        if is_intact {
-            intact.push(claim.id);
+        for j in unmarked.overlapping(i) {
+            is_conflicted[j] = true;
+            unmarked.remove(j);
         }
+        if active.overlaps_any(i) {
+            is_conflicted[i] = true;
+        } else {
+            unmarked.insert(i);
+        }
+        active.insert(i);
+    }
+    is_conflicted
+}
+
+/// The claims crossing the sweep line, both ordered by top edge and in a
+/// tree of their vertical spans. An active claim overlaps a new one if its
+/// top edge is inside the new claim's span, or its span stabs the new
+/// claim's top edge, so each check only finds claims that do overlap.
+struct ActiveClaims<'a> {
+    claims: &'a [Claim],
+    by_top: BTreeSet<(i128, usize)>,
+    spans: SpanTree,
+}
+
+impl<'a> ActiveClaims<'a> {
+    fn new(claims: &'a [Claim], ys: Vec<i128>) -> ActiveClaims<'a> {
+        ActiveClaims {
+            claims,
+            by_top: BTreeSet::new(),
+            spans: SpanTree::new(ys),
+        }
+    }
+
+    fn insert(&mut self, i: usize) {
+        let claim = &self.claims[i];
+        self.by_top.insert((claim.top(), i));
+        self.spans.insert(claim.top(), claim.bottom(), i);
+    }
+
+    fn remove(&mut self, i: usize) {
+        let claim = &self.claims[i];
+        self.by_top.remove(&(claim.top(), i));
+        self.spans.remove(claim.top(), claim.bottom(), i);
+    }
+
+    fn below_top(&self, i: usize) -> btree_set::Range<'_, (i128, usize)> {
+        // the active claims with their top edge inside claim i's span
+        let claim = &self.claims[i];
+        self.by_top.range((claim.top() + 1, 0)..(claim.bottom(), 0))
+    }
+
+    fn overlapping(&self, i: usize) -> Vec<usize> {
+        let mut found: Vec<usize> = self.below_top(i).map(|&(_, j)| j).collect();
+        found.extend(self.spans.stabbing(self.claims[i].top()));
+        found
+    }
+
+    fn overlaps_any(&self, i: usize) -> bool {
+        self.below_top(i).next().is_some() || self.spans.stabs(self.claims[i].top())
     }
-    intact
+}
+
+/// Claims' vertical spans, stored in a segment tree over the edges' y
+/// coordinates so the ones crossing any y can be listed without looking
+/// at the others
+struct SpanTree {
+    ys: Vec<i128>,               // every top and bottom edge, sorted
+    nodes: Vec<BTreeSet<usize>>, // claims covering each node's whole range
+}
+
+impl SpanTree {
+    fn new(ys: Vec<i128>) -> SpanTree {
+        let slots = ys.len().saturating_sub(1).max(1);
+        SpanTree {
+            ys,
+            nodes: vec![BTreeSet::new(); 4 * slots],
+        }
+    }
+
+    fn slots(&self) -> usize {
+        // slot s is the rows from ys[s] up to ys[s + 1]
+        self.ys.len().saturating_sub(1).max(1)
+    }
+
+    fn slot(&self, y: i128) -> usize {
+        self.ys.binary_search(&y).unwrap_or_else(|s| s.max(1) - 1)
+    }
+
+    fn insert(&mut self, top: i128, bottom: i128, claim: usize) {
+        let (from, to) = (self.slot(top), self.slot(bottom));
+        let slots = self.slots();
+        self.update(1, 0, slots, (from, to), claim, true);
+    }
+
+    fn remove(&mut self, top: i128, bottom: i128, claim: usize) {
+        let (from, to) = (self.slot(top), self.slot(bottom));
+        let slots = self.slots();
+        self.update(1, 0, slots, (from, to), claim, false);
+    }
+
+    fn update(
+        &mut self,
+        node: usize,
+        lo: usize,
+        hi: usize,
+        (from, to): (usize, usize),
+        claim: usize,
+        insert: bool,
+    ) {
+        /// Add or take away the claim in the fewest nodes that exactly
+        /// cover slots from..to, under this node covering lo..hi
+        if to <= lo || hi <= from {
+            return;
+        }
+        if from <= lo && hi <= to {
+            if insert {
+                self.nodes[node].insert(claim);
+            } else {
+                self.nodes[node].remove(&claim);
+            }
+            return;
+        }
+        let mid = (lo + hi) / 2;
+        self.update(2 * node, lo, mid, (from, to), claim, insert);
+        self.update(2 * node + 1, mid, hi, (from, to), claim, insert);
+    }
+
+    fn stabbing(&self, y: i128) -> Vec<usize> {
+        /// The claims whose span includes row y
+        self.path(y)
+            .into_iter()
+            .flat_map(|node| self.nodes[node].iter().cloned())
+            .collect()
+    }
+
+    fn stabs(&self, y: i128) -> bool {
+        /// Whether any claim's span includes row y
+        self.path(y)
+            .into_iter()
+            .any(|node| !self.nodes[node].is_empty())
+    }
+
+    fn path(&self, y: i128) -> Vec<usize> {
+        /// The nodes on the way down to row y's slot, which hold all the
+        /// claims whose span includes it
+        let mut path: Vec<usize> = Vec::new();
+        if self.ys.is_empty() || y < self.ys[0] || y >= self.ys[self.ys.len() - 1] {
+            return path;
+        }
+        let slot = self.slot(y);
+        let (mut node, mut lo, mut hi) = (1, 0, self.slots());
+        loop {
+            path.push(node);
+            if hi - lo == 1 {
+                return path;
+            }
+            let mid = (lo + hi) / 2;
+            if slot < mid {
+                node *= 2;
+                hi = mid;
+            } else {
+                node = 2 * node + 1;
+                lo = mid;
+            }
+        }
+    }
+}
+
+fn claims_covering(claims: &[Claim], x: i64, y: i64) -> Vec<u64> {
+    /// Given claims, return IDs of the ones that claim the square at (x, y)
+    claims
+        .iter()
+        .filter(|claim| claim.covers(x, y))
+        .map(|claim| claim.id)
+        .collect()
+}
+
+/// Which claims overlap which, and by how much
+struct ConflictGraph {
+    ids: Vec<u64>,
+    edges: Vec<(usize, usize, u128)>, // (claim index, claim index, shared area)
+}
+
+impl ConflictGraph {
+    fn new(claims: &[Claim]) -> ConflictGraph {
+        ConflictGraph {
+            ids: claims.iter().map(|claim| claim.id).collect(),
+            edges: overlapping_pairs(claims)
+                .into_iter()
+                .map(|(i, j)| (i, j, claims[i].shared_area(&claims[j])))
+                .collect(),
+        }
+    }
+
+    fn conflicts(&self) -> Vec<BTreeMap<u64, u128>> {
+        /// For each claim, the IDs of the claims it overlaps, with the shared area
+        let mut conflicts: Vec<BTreeMap<u64, u128>> = vec![BTreeMap::new(); self.ids.len()];
+        for &(i, j, area) in &self.edges {
+            conflicts[i].insert(self.ids[j], area);
+            conflicts[j].insert(self.ids[i], area);
+        }
+        conflicts
+    }
+
+    fn to_edge_list(&self) -> String {
+        /// One line per overlapping pair: "id id area"
+        self.edges
+            .iter()
+            .map(|&(i, j, area)| format!("{} {} {}\n", self.ids[i], self.ids[j], area))
+            .collect()
+    }
+
+    fn to_dot(&self) -> String {
+        /// A Graphviz graph of every claim, with overlaps as edges labeled by area
+        let mut dot = String::from("graph claims {\n");
+        for id in &self.ids {
+            dot.push_str(&format!("    {};\n", id));
+        }
+        for &(i, j, area) in &self.edges {
+            dot.push_str(&format!(
+                "    {} -- {} [label=\"{}\"];\n",
+                self.ids[i], self.ids[j], area
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn largest_cluster(&self) -> Vec<u64> {
+        /// IDs of the largest group of claims connected by overlaps
+        /// (empty if nothing overlaps; ties go to the cluster with the earliest claim)
+        let mut parent: Vec<usize> = (0..self.ids.len()).collect();
+        fn root(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+        for &(i, j, _) in &self.edges {
+            let (r_i, r_j) = (root(&mut parent, i), root(&mut parent, j));
+            parent[r_i.max(r_j)] = r_i.min(r_j);
+        }
+        let mut clusters: BTreeMap<usize, Vec<u64>> = BTreeMap::new();
+        for i in 0..self.ids.len() {
+            let r = root(&mut parent, i);
+            clusters.entry(r).or_default().push(self.ids[i]);
+        }
+        let mut largest: Vec<u64> = Vec::new();
+        for cluster in clusters.into_values() {
+            if cluster.len() > 1 && cluster.len() > largest.len() {
+                largest = cluster;
+            }
+        }
+        largest
+    }
+}
+
+fn find_intact(claims: &[Claim]) -> Vec<u64> {
+    /// Given claims, return IDs that are intact
+    claims
+        .iter()
+        .zip(conflicted(claims))
+        .filter(|(_, conflicted)| !conflicted)
+        .map(|(claim, _)| claim.id)
+        .collect()
 }
 
 #[cfg(test)]
@@ -165,7 +623,7 @@ mod tests {
     fn test_count_overlapping() {
         struct TestCase {
             input: Vec<Claim>,
-            output: usize,
+            output: u128,
         }
 
         let cases: Vec<TestCase> = vec![TestCase {
@@ -200,6 +658,146 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_overlap_matches_grid() {
+        // the overlap worked out square by square on graph paper
+        struct TestCase {
+            input: Vec<&'static str>,
+            area: u128,
+            intact: Vec<u64>,
+            pairs: Vec<(usize, usize)>,
+        }
+
+        let cases: Vec<TestCase> = vec![
+            // only touching along edges
+            TestCase {
+                input: vec!["#1 @ 0,0: 2x2", "#2 @ 2,0: 2x2", "#3 @ 0,2: 2x2"],
+                area: 0,
+                intact: vec![1, 2, 3],
+                pairs: vec![],
+            },
+            // one inside another
+            TestCase {
+                input: vec!["#1 @ 0,0: 5x5", "#2 @ 1,1: 2x2", "#3 @ 6,6: 1x1"],
+                area: 4,
+                intact: vec![3],
+                pairs: vec![(0, 1)],
+            },
+            // crossing, with neither corner inside the other
+            TestCase {
+                input: vec!["#1 @ 0,2: 6x2", "#2 @ 2,0: 2x6"],
+                area: 4,
+                intact: vec![],
+                pairs: vec![(0, 1)],
+            },
+            // an empty claim, and a corner square shared around the origin
+            TestCase {
+                input: vec!["#1 @ -3,-3: 4x4", "#2 @ 0,0: 0x5", "#3 @ 0,0: 3x3"],
+                area: 1,
+                intact: vec![2],
+                pairs: vec![(0, 2)],
+            },
+            // the same claim twice, and a third just catching both
+            TestCase {
+                input: vec![
+                    "#1 @ 1,1: 3x3",
+                    "#2 @ 1,1: 3x3",
+                    "#3 @ 3,3: 2x2",
+                    "#4 @ 10,0: 1x1",
+                ],
+                area: 9,
+                intact: vec![4],
+                pairs: vec![(0, 1), (0, 2), (1, 2)],
+            },
+            // a tall claim stabbed from the side, and one beside them
+            TestCase {
+                input: vec!["#1 @ 0,0: 1x10", "#2 @ 0,4: 3x1", "#3 @ 2,0: 1x3"],
+                area: 1,
+                intact: vec![3],
+                pairs: vec![(0, 1)],
+            },
+        ];
+
+        for case in &cases[..] {
+            let claims: Vec<Claim> = case.input.iter().map(|s| s.parse().unwrap()).collect();
+            assert_eq!(case.area, count_overlapping(&claims));
+            assert_eq!(case.intact, find_intact(&claims));
+            assert_eq!(case.pairs, overlapping_pairs(&claims));
+        }
+    }
+
+    #[test]
+    fn test_huge_claims() {
+        let claims = [
+            "#1 @ -9223372036854775808,0: 18446744073709551615x3",
+            "#2 @ 9223372036854775806,1: 10x10",
+            "#3 @ 0,-5: 1x5",
+        ]
+        .iter()
+        .map(|s| s.parse::<Claim>().unwrap())
+        .collect::<Vec<Claim>>();
+        // claim 2 sticks out past claim 1's right edge, which is at 2^63 - 1
+        assert_eq!(2, count_overlapping(&claims));
+        assert_eq!(vec![3], find_intact(&claims));
+    }
+
+    fn example_claims() -> Vec<Claim> {
+        [
+            "#1 @ 1,3: 4x4",
+            "#2 @ 3,1: 4x4",
+            "#3 @ 5,5: 2x2",
+            "#4 @ 6,6: 2x2",
+            "#5 @ 2,4: 1x1",
+        ]
+        .iter()
+        .map(|s| s.parse::<Claim>().unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let graph = ConflictGraph::new(&example_claims());
+        let conflicts = graph.conflicts();
+        let expected: Vec<Vec<(u64, u128)>> = vec![
+            vec![(2, 4), (5, 1)],
+            vec![(1, 4)],
+            vec![(4, 1)],
+            vec![(3, 1)],
+            vec![(1, 1)],
+        ];
+        for (expected, actual) in expected.iter().zip(conflicts) {
+            assert_eq!(*expected, actual.into_iter().collect::<Vec<_>>());
+        }
+        assert_eq!("1 2 4\n1 5 1\n3 4 1\n", graph.to_edge_list());
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("graph claims {\n"));
+        assert!(dot.contains("    3;\n"));
+        assert!(dot.contains("    1 -- 5 [label=\"1\"];\n"));
+    }
+
+    #[test]
+    fn test_claims_covering() {
+        let claims = example_claims();
+        assert_eq!(vec![1, 2], claims_covering(&claims, 3, 3));
+        assert_eq!(vec![1, 5], claims_covering(&claims, 2, 4));
+        assert_eq!(vec![3, 4], claims_covering(&claims, 6, 6));
+        assert_eq!(Vec::<u64>::new(), claims_covering(&claims, 0, 0));
+        assert_eq!(Vec::<u64>::new(), claims_covering(&claims, 7, 1)); // right edge is exclusive
+    }
+
+    #[test]
+    fn test_largest_cluster() {
+        assert_eq!(
+            vec![1, 2, 5],
+            ConflictGraph::new(&example_claims()).largest_cluster()
+        );
+        let apart = vec![
+            "#1 @ 0,0: 1x1".parse::<Claim>().unwrap(),
+            "#2 @ 1,0: 1x1".parse::<Claim>().unwrap(),
+        ];
+        assert!(ConflictGraph::new(&apart).largest_cluster().is_empty());
+    }
+
     #[test]
     fn test_find_intact() {
         struct TestCase {
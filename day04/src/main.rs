@@ -5,26 +5,49 @@ extern crate chrono;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::env;
+use std::fmt;
 use std::fs;
 use std::num::ParseIntError;
 use std::str::FromStr;
 
-use chrono::{NaiveDateTime, ParseError, Timelike};
+use chrono::{Duration, NaiveDate, NaiveDateTime, ParseError, Timelike};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let task = &args[1];
     let filename = &args[2];
 
-    let records: Vec<GuardRecord> = fs::read_to_string(filename)
-        .unwrap()
-        .split('\n')
-        .map(|s| s.parse::<GuardRecord>())
-        .filter(|x| x.is_ok())
-        .map(|x| x.unwrap()) // I feel like there should be an idiom for this and the line above
-        .collect();
+    let (records, unparseable) = read_log(&fs::read_to_string(filename).unwrap());
+    for diagnostic in &unparseable {
+        eprintln!("{}", diagnostic);
+    }
 
-    let naps = collect_naps(&records);
+    if task == "validate" {
+        for diagnostic in validate(&records) {
+            println!("{}", diagnostic);
+        }
+        return;
+    } else if task == "report" || task == "csv" || task == "timeline" {
+        let shifts = collect_shifts(&records);
+        let reports = guard_reports(&shifts);
+        if task == "report" {
+            for report in &reports {
+                println!("{}", report);
+            }
+        } else if task == "csv" {
+            print!("{}", reports_to_csv(&reports));
+        } else {
+            print!("{}", render_timeline(&shifts));
+        }
+        return;
+    }
+
+    let lenient = args.get(3).is_some_and(|mode| mode == "lenient");
+    let naps = if lenient {
+        collect_naps_lenient(&records)
+    } else {
+        collect_naps(&records)
+    };
 
     if task == "sleepiest" {
         let guard = sleepiest_guard(&naps);
@@ -64,10 +87,12 @@ impl PartialOrd for GuardRecord {
 }
 
 #[derive(Debug)]
+#[allow(dead_code)] // only read through Debug
 enum ParseRecordError {
     Chrono(ParseError),
     Number(ParseIntError),
     Empty,
+    NoTimestamp,
 }
 
 impl From<ParseError> for ParseRecordError {
@@ -91,7 +116,8 @@ impl FromStr for GuardRecord {
         }
 
         // assume starts with "[YYYY-mm-dd HH:MM]"
-        let timestamp = NaiveDateTime::parse_from_str(&s[1..17], "%Y-%m-%d %H:%M")?;
+        let raw_timestamp = s.get(1..17).ok_or(ParseRecordError::NoTimestamp)?;
+        let timestamp = NaiveDateTime::parse_from_str(raw_timestamp, "%Y-%m-%d %H:%M")?;
 
         let event = {
             if s.contains("falls asleep") {
@@ -104,7 +130,7 @@ impl FromStr for GuardRecord {
                     .split_whitespace()
                     .find(|&x| x.starts_with('#'))
                     .unwrap_or("")
-                    .trim_left_matches('#')
+                    .trim_start_matches('#')
                     .parse::<u32>()?;
                 GuardEvent::StartShift(n)
             }
@@ -154,6 +180,195 @@ fn collect_naps(records: &[GuardRecord]) -> Vec<Nap> {
     naps
 }
 
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Diagnostic {
+    Unparseable {
+        line: usize, // 1-based line number
+        text: String,
+    },
+    Orphan(GuardRecord), // sleeping or waking before any shift starts
+    DoubleSleep {
+        asleep: NaiveDateTime,
+        again: NaiveDateTime,
+    },
+    WakeWithoutSleep(NaiveDateTime),
+    // the shift or log ended first
+    UnfinishedNap {
+        guard: u32,
+        start: NaiveDateTime,
+    },
+    CrossesMidnight {
+        guard: u32,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    },
+    CrossesHour {
+        guard: u32,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Diagnostic::Unparseable { line, text } => {
+                write!(f, "line {}: couldn't parse '{}'", line, text)
+            }
+            Diagnostic::Orphan(record) => write!(
+                f,
+                "{}: {:?} before any guard's shift started",
+                record.timestamp, record.event
+            ),
+            Diagnostic::DoubleSleep { asleep, again } => write!(
+                f,
+                "{}: fell asleep again, already asleep since {}",
+                again, asleep
+            ),
+            Diagnostic::WakeWithoutSleep(t) => write!(f, "{}: woke up without falling asleep", t),
+            Diagnostic::UnfinishedNap { guard, start } => write!(
+                f,
+                "{}: guard #{} fell asleep and never woke up",
+                start, guard
+            ),
+            Diagnostic::CrossesMidnight { guard, start, end } => write!(
+                f,
+                "{}: guard #{} napped past midnight, until {}",
+                start, guard, end
+            ),
+            Diagnostic::CrossesHour { guard, start, end } => write!(
+                f,
+                "{}: guard #{} napped into another hour, until {}",
+                start, guard, end
+            ),
+        }
+    }
+}
+
+fn read_log(s: &str) -> (Vec<GuardRecord>, Vec<Diagnostic>) {
+    /// Parse every line of a log, keeping track of any that don't parse
+    let mut records: Vec<GuardRecord> = Vec::new();
+    let mut unparseable: Vec<Diagnostic> = Vec::new();
+    for (i, line) in s.split('\n').enumerate() {
+        match line.parse::<GuardRecord>() {
+            Ok(record) => records.push(record),
+            Err(ParseRecordError::Empty) => (),
+            Err(_) => unparseable.push(Diagnostic::Unparseable {
+                line: i + 1,
+                text: line.to_string(),
+            }),
+        }
+    }
+    (records, unparseable)
+}
+
+fn split_nap(guard: u32, start: NaiveDateTime, end: NaiveDateTime) -> Vec<Nap> {
+    /// Break a nap into pieces that each fit in one hour
+    /// A piece running to the end of its hour ends at minute 60
+    let mut naps: Vec<Nap> = Vec::new();
+    let mut t = start;
+    while t < end {
+        let hour_start = t.with_minute(0).unwrap().with_second(0).unwrap();
+        let next_hour = hour_start + Duration::hours(1);
+        naps.push(Nap {
+            guard,
+            start: t.minute(),
+            end: if end >= next_hour { 60 } else { end.minute() },
+        });
+        t = next_hour;
+    }
+    naps
+}
+
+fn midnight_naps(guard: u32, start: NaiveDateTime, end: NaiveDateTime) -> Vec<Nap> {
+    /// The parts of a nap within the midnight hour of each day it touches,
+    /// the only minutes that count (like collect_shifts)
+    let mut naps: Vec<Nap> = Vec::new();
+    let mut date = start.date();
+    while date <= end.date() {
+        let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+        let one_am = midnight + Duration::hours(1);
+        let (from, to) = (start.max(midnight), end.min(one_am));
+        if from < to {
+            naps.extend(split_nap(guard, from, to));
+        }
+        date = date.succ_opt().unwrap();
+    }
+    naps
+}
+
+fn walk_log(records: &[GuardRecord]) -> (Vec<Nap>, Vec<Diagnostic>) {
+    /// Go through the log in order, collecting naps along with anything odd
+    /// Naps crossing an hour (or midnight) are reported, and only their
+    /// minutes in the midnight hour are kept
+    let mut naps: Vec<Nap> = Vec::new();
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+
+    let mut sorted_records = records.to_vec();
+    sorted_records.sort();
+
+    let mut guard: Option<u32> = None;
+    let mut asleep: Option<NaiveDateTime> = None;
+    for record in sorted_records {
+        let t = record.timestamp;
+        match (record.event.clone(), guard) {
+            (GuardEvent::StartShift(g), _) => {
+                if let (Some(start), Some(old_guard)) = (asleep, guard) {
+                    diagnostics.push(Diagnostic::UnfinishedNap {
+                        guard: old_guard,
+                        start,
+                    });
+                }
+                guard = Some(g);
+                asleep = None;
+            }
+            (_, None) => diagnostics.push(Diagnostic::Orphan(record)),
+            (GuardEvent::FallAsleep, Some(_)) => match asleep {
+                Some(since) => diagnostics.push(Diagnostic::DoubleSleep {
+                    asleep: since,
+                    again: t,
+                }),
+                None => asleep = Some(t),
+            },
+            (GuardEvent::WakeUp, Some(g)) => match asleep {
+                None => diagnostics.push(Diagnostic::WakeWithoutSleep(t)),
+                Some(start) => {
+                    if start.date() != t.date() {
+                        diagnostics.push(Diagnostic::CrossesMidnight {
+                            guard: g,
+                            start,
+                            end: t,
+                        });
+                    } else if start.hour() != t.hour() {
+                        diagnostics.push(Diagnostic::CrossesHour {
+                            guard: g,
+                            start,
+                            end: t,
+                        });
+                    }
+                    naps.extend(midnight_naps(g, start, t));
+                    asleep = None;
+                }
+            },
+        }
+    }
+    if let (Some(start), Some(g)) = (asleep, guard) {
+        diagnostics.push(Diagnostic::UnfinishedNap { guard: g, start });
+    }
+    (naps, diagnostics)
+}
+
+fn validate(records: &[GuardRecord]) -> Vec<Diagnostic> {
+    /// Check a log for events that don't make sense together
+    walk_log(records).1
+}
+
+fn collect_naps_lenient(records: &[GuardRecord]) -> Vec<Nap> {
+    /// Like collect_naps, but skips nonsensical events rather than guessing,
+    /// and keeps the midnight hour of naps crossing hours rather than panicking
+    walk_log(records).0
+}
+
 fn sleepiest_guard(naps: &[Nap]) -> u32 {
     /// Given a bunch of naps, return the guard that sleeps the most (total)
     let mut guard_naptime: HashMap<u32, u64> = HashMap::new();
@@ -198,6 +413,188 @@ fn most_predictable(naps: &[Nap]) -> (u32, u32) {
     (guard.to_owned(), minute.to_owned())
 }
 
+#[derive(Debug, PartialEq, Eq)]
+struct Shift {
+    date: NaiveDate, // the date of the midnight hour the shift covers
+    guard: u32,
+    naps: Vec<Nap>, // only the parts of naps within the midnight hour
+}
+
+fn collect_shifts(records: &[GuardRecord]) -> Vec<Shift> {
+    /// Group the log into shifts, each with its naps during the midnight hour
+    /// Events outside any shift are skipped (see validate)
+    let mut sorted_records = records.to_vec();
+    sorted_records.sort();
+
+    let mut shifts: Vec<Shift> = Vec::new();
+    let mut asleep: Option<NaiveDateTime> = None;
+    for record in sorted_records {
+        let t = record.timestamp;
+        match record.event {
+            GuardEvent::StartShift(guard) => {
+                // shifts starting before midnight are for the next day
+                let date = if t.hour() != 0 {
+                    t.date().succ_opt().unwrap()
+                } else {
+                    t.date()
+                };
+                shifts.push(Shift {
+                    date,
+                    guard,
+                    naps: Vec::new(),
+                });
+                asleep = None;
+            }
+            GuardEvent::FallAsleep => asleep = asleep.or(Some(t)),
+            GuardEvent::WakeUp => {
+                if let (Some(start), Some(shift)) = (asleep, shifts.last_mut()) {
+                    let midnight = shift.date.and_hms_opt(0, 0, 0).unwrap();
+                    let one_am = midnight + Duration::hours(1);
+                    let (start, end) = (start.max(midnight), t.min(one_am));
+                    if start < end {
+                        shift.naps.extend(split_nap(shift.guard, start, end));
+                    }
+                }
+                asleep = None;
+            }
+        }
+    }
+    shifts
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct GuardReport {
+    guard: u32,
+    shifts: usize,
+    minutes_asleep: u32,
+    histogram: [u32; 60], // how many shifts the guard was asleep at each minute
+    longest_nap: u32,     // minutes
+    sleepy_streak: usize, // most consecutive shifts (by this guard) with a nap
+}
+
+fn guard_reports(shifts: &[Shift]) -> Vec<GuardReport> {
+    /// Summarize each guard's sleeping, sorted by guard number
+    let mut reports: HashMap<u32, GuardReport> = HashMap::new();
+    let mut streaks: HashMap<u32, usize> = HashMap::new();
+    for shift in shifts {
+        let report = reports.entry(shift.guard).or_insert(GuardReport {
+            guard: shift.guard,
+            shifts: 0,
+            minutes_asleep: 0,
+            histogram: [0; 60],
+            longest_nap: 0,
+            sleepy_streak: 0,
+        });
+        report.shifts += 1;
+        for nap in &shift.naps {
+            report.minutes_asleep += nap.end - nap.start;
+            report.longest_nap = report.longest_nap.max(nap.end - nap.start);
+            for min in nap.start..nap.end {
+                report.histogram[min as usize] += 1;
+            }
+        }
+        let streak = streaks.entry(shift.guard).or_insert(0);
+        *streak = if shift.naps.is_empty() {
+            0
+        } else {
+            *streak + 1
+        };
+        report.sleepy_streak = report.sleepy_streak.max(*streak);
+    }
+    let mut reports: Vec<GuardReport> = reports.into_values().collect();
+    reports.sort_by_key(|report| report.guard);
+    reports
+}
+
+impl fmt::Display for GuardReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Guard #{}: {} shifts, {} minutes asleep, longest nap {}, {} sleepy shifts in a row",
+            self.guard, self.shifts, self.minutes_asleep, self.longest_nap, self.sleepy_streak
+        )?;
+        // one row per level of the histogram, tallest first
+        let max = self.histogram.iter().max().cloned().unwrap_or(0);
+        for level in (1..=max).rev() {
+            let row: String = self
+                .histogram
+                .iter()
+                .map(|&n| if n >= level { '#' } else { ' ' })
+                .collect();
+            writeln!(f, "{:>3} {}", level, row.trim_end())?;
+        }
+        write!(f, "    {}", minute_ruler().1)
+    }
+}
+
+fn minute_ruler() -> (String, String) {
+    /// The tens and ones digits of minutes 0-59, for headers
+    let tens: String = (0..60).map(|m| char::from(b'0' + m / 10)).collect();
+    let ones: String = (0..60).map(|m| char::from(b'0' + m % 10)).collect();
+    (tens, ones)
+}
+
+fn reports_to_csv(reports: &[GuardReport]) -> String {
+    /// One row per guard, with a column per minute of the histogram
+    let minutes: Vec<String> = (0..60).map(|m| format!("m{:02}", m)).collect();
+    let mut csv = format!(
+        "guard,shifts,minutes_asleep,longest_nap,sleepy_streak,{}\n",
+        minutes.join(",")
+    );
+    for report in reports {
+        let histogram: Vec<String> = report.histogram.iter().map(|n| n.to_string()).collect();
+        csv.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            report.guard,
+            report.shifts,
+            report.minutes_asleep,
+            report.longest_nap,
+            report.sleepy_streak,
+            histogram.join(",")
+        ));
+    }
+    csv
+}
+
+fn render_timeline(shifts: &[Shift]) -> String {
+    /// Render shifts like the puzzle's Date / ID / Minute table
+    let (tens, ones) = minute_ruler();
+    let id_width = shifts
+        .iter()
+        .map(|shift| format!("#{}", shift.guard).len())
+        .max()
+        .unwrap_or(0)
+        .max(3);
+    let indent = " ".repeat(5 + 2 + id_width + 2);
+    let mut timeline = format!(
+        "Date   {:<width$}  Minute\n{}{}\n{}{}\n",
+        "ID",
+        indent,
+        tens,
+        indent,
+        ones,
+        width = id_width
+    );
+    let mut sorted: Vec<&Shift> = shifts.iter().collect();
+    sorted.sort_by_key(|shift| shift.date);
+    for shift in sorted {
+        let mut minutes = ['.'; 60];
+        for nap in &shift.naps {
+            for min in nap.start..nap.end {
+                minutes[min as usize] = '#';
+            }
+        }
+        timeline.push_str(&format!(
+            "{}  {:<width$}  {}\n",
+            shift.date.format("%m-%d"),
+            format!("#{}", shift.guard),
+            minutes.iter().collect::<String>(),
+            width = id_width
+        ));
+    }
+    timeline
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -242,7 +639,11 @@ mod tests {
 
     #[test]
     fn test_record_parse_error() {
-        let cases: Vec<String> = vec![String::from("")];
+        let cases: Vec<String> = vec![
+            String::from(""),
+            String::from("wakes up"),
+            String::from("[1518-11-01 00:00] Guard begins shift"),
+        ];
 
         for ref case in &cases[..] {
             assert!(case.parse::<GuardRecord>().is_err());
@@ -365,6 +766,212 @@ mod tests {
         }
     }
 
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(1518, 11, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    fn record(day: u32, hour: u32, minute: u32, event: GuardEvent) -> GuardRecord {
+        GuardRecord {
+            timestamp: at(day, hour, minute),
+            event,
+        }
+    }
+
+    #[test]
+    fn test_read_log() {
+        let (records, unparseable) = read_log(
+            "[1518-11-01 00:00] Guard #10 begins shift\n\
+             bogus\n\
+             [1518-11-01 00:05] falls asleep\n\
+             \n",
+        );
+        assert_eq!(2, records.len());
+        assert_eq!(
+            vec![Diagnostic::Unparseable {
+                line: 2,
+                text: String::from("bogus"),
+            }],
+            unparseable
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let records = vec![
+            record(1, 23, 50, GuardEvent::FallAsleep),
+            record(1, 23, 55, GuardEvent::StartShift(10)),
+            record(1, 23, 58, GuardEvent::WakeUp),
+            record(2, 0, 5, GuardEvent::FallAsleep),
+            record(2, 0, 10, GuardEvent::FallAsleep),
+            record(2, 0, 20, GuardEvent::WakeUp),
+            record(2, 23, 50, GuardEvent::StartShift(99)),
+            record(2, 23, 55, GuardEvent::FallAsleep),
+            record(3, 0, 10, GuardEvent::WakeUp),
+            record(3, 0, 50, GuardEvent::FallAsleep),
+            record(3, 1, 5, GuardEvent::WakeUp),
+            record(3, 1, 30, GuardEvent::FallAsleep),
+        ];
+        assert_eq!(
+            vec![
+                Diagnostic::Orphan(record(1, 23, 50, GuardEvent::FallAsleep)),
+                Diagnostic::WakeWithoutSleep(at(1, 23, 58)),
+                Diagnostic::DoubleSleep {
+                    asleep: at(2, 0, 5),
+                    again: at(2, 0, 10),
+                },
+                Diagnostic::CrossesMidnight {
+                    guard: 99,
+                    start: at(2, 23, 55),
+                    end: at(3, 0, 10),
+                },
+                Diagnostic::CrossesHour {
+                    guard: 99,
+                    start: at(3, 0, 50),
+                    end: at(3, 1, 5),
+                },
+                Diagnostic::UnfinishedNap {
+                    guard: 99,
+                    start: at(3, 1, 30),
+                },
+            ],
+            validate(&records)
+        );
+        assert_eq!(
+            vec![
+                Nap {
+                    guard: 10,
+                    start: 5,
+                    end: 20,
+                },
+                Nap {
+                    guard: 99,
+                    start: 0,
+                    end: 10,
+                },
+                Nap {
+                    guard: 99,
+                    start: 50,
+                    end: 60,
+                },
+            ],
+            collect_naps_lenient(&records)
+        );
+    }
+
+    const EXAMPLE: &str = "[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-01 00:05] falls asleep
+[1518-11-01 00:25] wakes up
+[1518-11-01 00:30] falls asleep
+[1518-11-01 00:55] wakes up
+[1518-11-01 23:58] Guard #99 begins shift
+[1518-11-02 00:40] falls asleep
+[1518-11-02 00:50] wakes up
+[1518-11-03 00:05] Guard #10 begins shift
+[1518-11-03 00:24] falls asleep
+[1518-11-03 00:29] wakes up
+[1518-11-04 00:02] Guard #99 begins shift
+[1518-11-04 00:36] falls asleep
+[1518-11-04 00:46] wakes up
+[1518-11-05 00:03] Guard #99 begins shift
+[1518-11-05 00:45] falls asleep
+[1518-11-05 00:55] wakes up
+";
+
+    #[test]
+    fn test_collect_shifts() {
+        let shifts = collect_shifts(&read_log(EXAMPLE).0);
+        assert_eq!(5, shifts.len());
+        assert_eq!(at(2, 0, 0).date(), shifts[1].date);
+        assert_eq!(99, shifts[1].guard);
+        assert_eq!(
+            vec![Nap {
+                guard: 99,
+                start: 40,
+                end: 50,
+            }],
+            shifts[1].naps
+        );
+        assert_eq!(2, shifts[0].naps.len());
+
+        // a shift starting well before midnight is still for the next day
+        let shifts = collect_shifts(&[
+            record(3, 22, 15, GuardEvent::StartShift(7)),
+            record(4, 0, 10, GuardEvent::FallAsleep),
+            record(4, 0, 20, GuardEvent::WakeUp),
+        ]);
+        assert_eq!(at(4, 0, 0).date(), shifts[0].date);
+        assert_eq!(
+            vec![Nap {
+                guard: 7,
+                start: 10,
+                end: 20,
+            }],
+            shifts[0].naps
+        );
+    }
+
+    #[test]
+    fn test_guard_reports() {
+        let reports = guard_reports(&collect_shifts(&read_log(EXAMPLE).0));
+        assert_eq!(2, reports.len());
+        assert_eq!(10, reports[0].guard);
+        assert_eq!(2, reports[0].shifts);
+        assert_eq!(50, reports[0].minutes_asleep);
+        assert_eq!(25, reports[0].longest_nap);
+        assert_eq!(2, reports[0].histogram[24]);
+        assert_eq!(99, reports[1].guard);
+        assert_eq!(30, reports[1].minutes_asleep);
+        assert_eq!(3, reports[1].histogram[45]);
+        assert_eq!(3, reports[1].sleepy_streak);
+    }
+
+    #[test]
+    fn test_render_timeline() {
+        let timeline = render_timeline(&collect_shifts(&read_log(EXAMPLE).0));
+        let lines: Vec<&str> = timeline.lines().collect();
+        assert_eq!(
+            vec![
+                "Date   ID   Minute",
+                "            000000000011111111112222222222333333333344444444445555555555",
+                "            012345678901234567890123456789012345678901234567890123456789",
+                "11-01  #10  .....####################.....#########################.....",
+                "11-02  #99  ........................................##########..........",
+                "11-03  #10  ........................#####...............................",
+                "11-04  #99  ....................................##########..............",
+                "11-05  #99  .............................................##########.....",
+            ],
+            lines
+        );
+
+        // longer IDs widen the column, header included
+        let timeline = render_timeline(&collect_shifts(&[
+            record(1, 23, 58, GuardEvent::StartShift(1234)),
+            record(2, 0, 5, GuardEvent::FallAsleep),
+            record(2, 0, 10, GuardEvent::WakeUp),
+        ]));
+        let lines: Vec<&str> = timeline.lines().collect();
+        assert_eq!("Date   ID     Minute", lines[0]);
+        assert_eq!(
+            format!("11-02  #1234  .....#####{}", ".".repeat(50)),
+            lines[3]
+        );
+        assert_eq!(lines[1].len(), "11-02  #1234  ".len() + 60);
+    }
+
+    #[test]
+    fn test_reports_to_csv() {
+        let csv = reports_to_csv(&guard_reports(&collect_shifts(&read_log(EXAMPLE).0)));
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(3, lines.len());
+        assert!(lines[0].starts_with("guard,shifts,minutes_asleep,longest_nap,sleepy_streak,m00,"));
+        assert!(lines[0].ends_with(",m59"));
+        assert!(lines[1].starts_with("10,2,50,25,2,0,0,0,0,0,1,"));
+        assert_eq!(65, lines[2].split(',').count());
+    }
+
     #[test]
     fn test_sleepiest_guard() {
         struct TestCase {
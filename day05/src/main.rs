@@ -1,59 +1,263 @@
 #![allow(unused_doc_comments)]
 
+use std::collections::VecDeque;
 use std::env;
 use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader};
+use std::thread;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let task = &args[1];
     let filename = &args[2];
 
+    if task == "react" {
+        // stream the file through the reducer rather than reading it all in
+        let file = BufReader::new(File::open(filename).unwrap());
+        let (consumed, remaining) = match args.get(3) {
+            Some(rules) => {
+                let rule = Pairs::parse(&fs::read_to_string(rules).unwrap());
+                let mut reducer = Reducer::new(&rule);
+                reducer.feed(file).unwrap();
+                (reducer.consumed(), reducer.units().len())
+            }
+            None => {
+                let mut reducer = Reducer::new(&OppositeCase);
+                reducer.feed(file).unwrap();
+                (reducer.consumed(), reducer.units().len())
+            }
+        };
+        println!("{} down to {}", consumed, remaining);
+        return;
+    }
+
     let raw = fs::read_to_string(filename).unwrap();
     let polymer = raw.trim();
 
-    if task == "react" {
-        let reacted = react(&polymer);
-        println!("{} down to {}", polymer.len(), reacted.len());
-    } else if task == "improve" {
-        let (unit, improved) = best_removal(&polymer);
+    if task == "improve" {
+        let (unit, improved) = best_removal(polymer);
         println!(
             "Remove '{}' and it reduces down to {}",
             unit,
             improved.len()
         );
+    } else if task == "rank" {
+        for (unit, reacted) in rank_removals(polymer) {
+            println!("{} {}", unit, reacted.len());
+        }
     } else {
         panic!("Don't know how to '{}'", task);
     }
 }
 
-fn react(s_orig: &str) -> String {
-    /// "react" the polymer represented by the string, removing opposite polarity pairs
-    // Keep 2 stacks, one with the original string and one initially empty.
-    // Compare the tops of the stacks. If they react, throw away both elements.
-    // If they don't react, move the element from the original string onto
-    // the new stack, and then repeat, until the original stack is exhausted
-    let mut old: Vec<char> = s_orig.chars().collect();
-    let mut new: Vec<char> = Vec::new();
-    old.reverse(); // we want to pop from the start of the original
-    while !old.is_empty() {
-        match (old.pop(), new.pop()) {
-            (None, None) => {}
-            (None, Some(_n)) => {} // we've exhausted the original string
-            (Some(o), None) => {
-                new.push(o); // move the top of the original to the new stack
-            }
-            (Some(o), Some(n)) => {
-                // compare the tops of the stacks
-                if !(o != n && (o.to_ascii_lowercase() == n || o.to_ascii_uppercase() == n)) {
-                    // no reaction, so put them both onto the new stack
-                    new.push(n);
-                    new.push(o);
+/// Decides what the units of a polymer are and which pairs of them react
+trait Rule {
+    type Unit;
+
+    /// The most characters a single unit can span
+    fn lookahead(&self) -> usize {
+        1
+    }
+
+    /// Split the unit off the front of `chars`, returning it (or None for
+    /// characters that aren't part of the polymer, like whitespace) and
+    /// how many characters it used. `chars` holds `lookahead()` characters,
+    /// or fewer at the end of the polymer.
+    fn unit(&self, chars: &[char]) -> (Option<Self::Unit>, usize);
+
+    fn reacts(&self, a: &Self::Unit, b: &Self::Unit) -> bool;
+}
+
+/// The puzzle's rule: a letter reacts with the same letter of opposite case
+struct OppositeCase;
+
+impl Rule for OppositeCase {
+    type Unit = char;
+
+    fn unit(&self, chars: &[char]) -> (Option<char>, usize) {
+        if chars[0].is_whitespace() {
+            (None, 1)
+        } else {
+            (Some(chars[0]), 1)
+        }
+    }
+
+    fn reacts(&self, a: &char, b: &char) -> bool {
+        a != b && a.eq_ignore_ascii_case(b)
+    }
+}
+
+/// An explicit list of reacting pairs, whose units may be several
+/// characters long; any other character is a unit that never reacts
+struct Pairs {
+    pairs: Vec<(String, String)>,
+    units: Vec<String>, // longest first, for greedy matching
+}
+
+impl Pairs {
+    fn new(pairs: &[(&str, &str)]) -> Pairs {
+        let mut units: Vec<String> = pairs
+            .iter()
+            .flat_map(|&(a, b)| vec![String::from(a), String::from(b)])
+            .filter(|unit| !unit.is_empty())
+            .collect();
+        units.sort_by_key(|unit| std::cmp::Reverse(unit.chars().count()));
+        units.dedup();
+        Pairs {
+            pairs: pairs
+                .iter()
+                .map(|&(a, b)| (String::from(a), String::from(b)))
+                .collect(),
+            units,
+        }
+    }
+
+    fn parse(s: &str) -> Pairs {
+        /// One pair per line, separated by whitespace, e.g. "ab BA"
+        let pairs: Vec<(&str, &str)> = s
+            .lines()
+            .filter_map(|line| {
+                let mut words = line.split_whitespace();
+                match (words.next(), words.next()) {
+                    (Some(a), Some(b)) => Some((a, b)),
+                    _ => None,
                 }
-                // if they reacted, they're both gone; nothing to push
+            })
+            .collect();
+        Pairs::new(&pairs)
+    }
+}
+
+impl Rule for Pairs {
+    type Unit = String;
+
+    fn lookahead(&self) -> usize {
+        self.units
+            .first()
+            .map_or(1, |unit| unit.chars().count().max(1))
+    }
+
+    fn unit(&self, chars: &[char]) -> (Option<String>, usize) {
+        for unit in &self.units {
+            let len = unit.chars().count();
+            if len <= chars.len() && unit.chars().eq(chars[..len].iter().cloned()) {
+                return (Some(unit.clone()), len);
             }
-        };
+        }
+        if chars[0].is_whitespace() {
+            (None, 1)
+        } else {
+            (Some(chars[0].to_string()), 1)
+        }
+    }
+
+    fn reacts(&self, a: &String, b: &String) -> bool {
+        self.pairs
+            .iter()
+            .any(|(x, y)| (x == a && y == b) || (x == b && y == a))
     }
-    new.iter().collect::<String>()
+}
+
+/// Reacts a polymer as it streams in, keeping only the surviving units
+struct Reducer<'a, R: Rule + 'a> {
+    rule: &'a R,
+    survivors: Vec<R::Unit>,
+    pending: VecDeque<char>, // characters not yet split into units
+    consumed: usize,         // units seen so far, reacted or not
+}
+
+impl<'a, R: Rule> Reducer<'a, R> {
+    fn new(rule: &'a R) -> Reducer<'a, R> {
+        Reducer {
+            rule,
+            survivors: Vec::new(),
+            pending: VecDeque::new(),
+            consumed: 0,
+        }
+    }
+
+    fn push(&mut self, unit: R::Unit) {
+        // add a unit to the end of the polymer, reacting it with the last survivor
+        self.consumed += 1;
+        match self.survivors.last() {
+            Some(last) if self.rule.reacts(last, &unit) => {
+                self.survivors.pop();
+            }
+            _ => self.survivors.push(unit),
+        }
+    }
+
+    fn push_char(&mut self, c: char) {
+        /// Add a character, splitting off units once there's enough lookahead
+        self.pending.push_back(c);
+        while self.pending.len() >= self.rule.lookahead() {
+            self.split_unit();
+        }
+    }
+
+    fn split_unit(&mut self) {
+        let (unit, used) = self.rule.unit(self.pending.make_contiguous());
+        self.pending.drain(..used.clamp(1, self.pending.len()));
+        if let Some(unit) = unit {
+            self.push(unit);
+        }
+    }
+
+    fn finish(&mut self) {
+        /// Split whatever characters are still waiting for lookahead
+        while !self.pending.is_empty() {
+            self.split_unit();
+        }
+    }
+
+    fn feed<S: BufRead>(&mut self, source: S) -> io::Result<()> {
+        /// Consume a UTF-8 polymer from any reader, to the end
+        let mut bytes = source.bytes();
+        while let Some(byte) = bytes.next() {
+            let first = byte?;
+            let len = match first.leading_ones() {
+                0 => 1,
+                n @ 2..=4 => n as usize,
+                _ => return Err(invalid_utf8()),
+            };
+            let mut buf = [first, 0, 0, 0];
+            for slot in buf.iter_mut().take(len).skip(1) {
+                *slot = bytes.next().unwrap_or_else(|| Err(invalid_utf8()))?;
+            }
+            let c = std::str::from_utf8(&buf[..len])
+                .map_err(|_| invalid_utf8())?
+                .chars()
+                .next()
+                .unwrap();
+            self.push_char(c);
+        }
+        self.finish();
+        Ok(())
+    }
+
+    fn consumed(&self) -> usize {
+        self.consumed
+    }
+
+    fn units(&self) -> &[R::Unit] {
+        &self.survivors
+    }
+}
+
+fn invalid_utf8() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "polymer is not valid UTF-8")
+}
+
+fn react(s_orig: &str) -> String {
+    /// "react" the polymer represented by the string, removing opposite polarity pairs
+    let mut reducer = Reducer::new(&OppositeCase);
+    for c in s_orig.chars() {
+        reducer.push_char(c);
+    }
+    reducer.finish();
+    reducer.units().iter().collect::<String>()
 }
 
 fn remove_unit(undesired: char, polymer: &str) -> String {
@@ -66,24 +270,48 @@ fn remove_unit(undesired: char, polymer: &str) -> String {
         .collect::<String>()
 }
 
-fn best_removal(polymer: &str) -> (char, String) {
-    /// Determine which unit we can remove from the polymer to get it as short as possible
-    let mut units: Vec<char> = polymer.to_lowercase().chars().collect();
+fn rank_removals(polymer: &str) -> Vec<(char, String)> {
+    /// React the polymer with each unit removed, shortest result first
+    // Removing a unit and reacting gives the same result whether or not the
+    // polymer was reacted first, so react once up front and each candidate
+    // starts from the (much shorter) reacted polymer.
+    let mut units: Vec<char> = polymer.chars().map(|c| c.to_ascii_lowercase()).collect();
     units.sort();
     units.dedup();
+    let reacted = react(polymer);
 
-    let mut best_unit: char = ' ';
-    let mut best_reacted = String::from(polymer);
+    let n_workers = thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(units.len())
+        .max(1);
+    let mut ranking: Vec<(char, String)> = thread::scope(|scope| {
+        let workers: Vec<_> = units
+            .chunks(units.len().div_ceil(n_workers).max(1))
+            .map(|chunk| {
+                let reacted = &reacted;
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|&unit| (unit, react(&remove_unit(unit, reacted))))
+                        .collect::<Vec<(char, String)>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect()
+    });
+    ranking.sort_by(|(a_unit, a), (b_unit, b)| a.len().cmp(&b.len()).then(a_unit.cmp(b_unit)));
+    ranking
+}
 
-    for unit in units {
-        let removed = remove_unit(unit, polymer);
-        let reacted = react(&removed);
-        if reacted.len() < best_reacted.len() {
-            best_unit = unit;
-            best_reacted = reacted;
-        }
-    }
-    (best_unit, best_reacted)
+fn best_removal(polymer: &str) -> (char, String) {
+    /// Determine which unit we can remove from the polymer to get it as short as possible
+    rank_removals(polymer)
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| (' ', String::from(polymer)))
 }
 
 #[cfg(test)]
@@ -125,6 +353,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_reducer_feed() {
+        let mut reducer = Reducer::new(&OppositeCase);
+        reducer.feed("dabAcCaCBAcCcaDA\n".as_bytes()).unwrap();
+        assert_eq!(16, reducer.consumed());
+        assert_eq!("dabCBAcaDA", reducer.units().iter().collect::<String>());
+
+        let mut reducer = Reducer::new(&OppositeCase);
+        assert!(reducer.feed(&b"ab\xffc"[..]).is_err());
+    }
+
+    #[test]
+    fn test_pairs() {
+        // multi-character units, and an alphabet beyond ASCII letters
+        let rule = Pairs::parse("ab ba\nα Ω\n");
+        let mut reducer = Reducer::new(&rule);
+        reducer.feed("xαabbaΩy".as_bytes()).unwrap();
+        assert_eq!(vec!["x", "y"], reducer.units());
+        assert_eq!(6, reducer.consumed());
+
+        // greedy matching prefers the longer unit
+        let rule = Pairs::new(&[("a", "A"), ("aa", "b")]);
+        let mut reducer = Reducer::new(&rule);
+        reducer.feed("aab".as_bytes()).unwrap();
+        assert!(reducer.units().is_empty());
+        let mut reducer = Reducer::new(&rule);
+        reducer.feed("aAb".as_bytes()).unwrap();
+        assert_eq!(vec!["b"], reducer.units());
+    }
+
     #[test]
     fn test_remove_unit() {
         struct TestCase {
@@ -158,6 +416,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_rank_removals() {
+        let ranking: Vec<(char, usize)> = rank_removals("dabAcCaCBAcCcaDA")
+            .into_iter()
+            .map(|(unit, reacted)| (unit, reacted.len()))
+            .collect();
+        assert_eq!(vec![('c', 4), ('a', 6), ('d', 6), ('b', 8)], ranking);
+        assert!(rank_removals("").is_empty());
+    }
+
     #[test]
     fn test_best_removal() {
         struct TestCase {
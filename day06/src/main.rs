@@ -1,5 +1,8 @@
 #![allow(unused_doc_comments)]
-use std::collections::{HashMap, HashSet};
+extern crate gridimage;
+
+use gridimage::{Frame, Palette, Rgb};
+use std::convert::TryFrom;
 use std::env;
 use std::fs;
 use std::num::ParseIntError;
@@ -13,99 +16,778 @@ fn main() {
     let points: Vec<Point> = fs::read_to_string(filename)
         .unwrap()
         .split('\n')
-        .map(|s| s.parse::<Point>())
-        .filter(|x| x.is_ok())
-        .map(|x| x.unwrap()) // I feel like there should be an idiom for this and the line above
+        .flat_map(|s| s.parse::<Point>())
         .collect();
 
     if task == "area" {
-        println!("{}", max_interior_area(&points));
+        let metric = parse_metric(args.get(3));
+        match max_interior_area_with(metric, &points) {
+            Some(area) => println!("{}", area),
+            None => println!("Every region is infinite"),
+        }
     } else if task == "proximal" {
-        println!("{}", proximal_area(10000, &points));
+        let max_distance = args.get(3).map_or(10000, |m| m.parse().unwrap());
+        let region = ProximalRegion::new(max_distance, &points);
+        match region.extent() {
+            Some((lo, hi)) => {
+                println!("{} ({},{} to {},{})", region.area(), lo.x, lo.y, hi.x, hi.y)
+            }
+            None => println!("0"),
+        }
+    } else if task == "map" {
+        let metric = parse_metric(args.get(3));
+        let max_distance = args.get(4).map(|m| m.parse().unwrap());
+        let map = AreaMap::new(&points, metric, max_distance);
+        print!("{}", map.render());
+        print!("{}", map.legend());
+    } else if task == "png" {
+        let out_file = args.get(3).expect("Need an output file");
+        let metric = parse_metric(args.get(4));
+        let max_distance = args.get(5).map(|m| m.parse().unwrap());
+        let map = AreaMap::new(&points, metric, max_distance);
+        map.to_frame(FRAME_SCALE)
+            .save_png(out_file)
+            .expect("Couldn't write image");
+        print!("{}", map.legend());
     } else {
         panic!("Don't know how to '{}'", task);
     }
 }
 
-fn max_interior_area(points: &[Point]) -> i32 {
-    /// compute the maximum nearest-neighbor area that isn't infinite
-    let x_lo = points.iter().map(|p| p.x).min().unwrap();
-    let x_hi = points.iter().map(|p| p.x).max().unwrap();
+static FRAME_SCALE: usize = 3;
 
-    let y_lo = points.iter().map(|p| p.y).min().unwrap();
-    let y_hi = points.iter().map(|p| p.y).max().unwrap();
+fn parse_metric(arg: Option<&String>) -> Metric {
+    match arg.map_or("manhattan", |m| m.as_str()) {
+        "manhattan" => Metric::Manhattan,
+        "chebyshev" => Metric::Chebyshev,
+        "euclidean" => Metric::EuclideanSquared,
+        m => panic!("Don't know the metric '{}'", m),
+    }
+}
 
-    let mut area_around: HashMap<Point, i32> = HashMap::new();
-    let mut uncontained: HashSet<Point> = HashSet::new();
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Metric {
+    Manhattan,
+    Chebyshev,        // diagonal steps count as one
+    EuclideanSquared, // squared, so it stays an integer
+}
 
-    // scan a grid that contains all points
-    for y in y_lo..=y_hi {
-        for x in x_lo..=x_hi {
-            // find the nearest neighbors to the grid spot
-            let p = Point::new(x, y);
-            let mut nearest_neighbors: Vec<(i32, Point)> = points
-                .iter()
-                .map(|other| (p.distance(*other), *other))
-                .collect();
+impl Metric {
+    fn distance(self, a: Point, b: Point) -> i64 {
+        let dx = (i64::from(a.x) - i64::from(b.x)).abs();
+        let dy = (i64::from(a.y) - i64::from(b.y)).abs();
+        match self {
+            Metric::Manhattan => dx + dy,
+            Metric::Chebyshev => dx.max(dy),
+            Metric::EuclideanSquared => dx * dx + dy * dy,
+        }
+    }
+
+    fn along_axis(self, d: i64) -> i64 {
+        /// The distance between points d apart along one axis, which
+        /// bounds the distance between any points d apart on either axis
+        match self {
+            Metric::Manhattan | Metric::Chebyshev => d,
+            Metric::EuclideanSquared => d * d,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Owner {
+    Site(usize), // index of the uniquely nearest point
+    Tie,         // two or more points are equally near
+}
+
+/// Buckets the points on a coarse grid so the nearest ones to a cell can
+/// be found by searching outward bucket by bucket
+struct NearestIndex<'a> {
+    points: &'a [Point],
+    metric: Metric,
+    origin: Point,
+    bucket_size: i32,
+    n_x: i32,
+    n_y: i32,
+    buckets: Vec<Vec<usize>>,
+}
+
+impl<'a> NearestIndex<'a> {
+    fn new(points: &'a [Point], metric: Metric) -> NearestIndex<'a> {
+        let (lo, hi) = bounding_box(points);
+        let width = i64::from(hi.x - lo.x) + 1;
+        let height = i64::from(hi.y - lo.y) + 1;
+        // aim for about one point per bucket
+        let bucket_size = (((width * height) as f64 / points.len() as f64).sqrt() as i32).max(1);
+        let n_x = (width / i64::from(bucket_size)) as i32 + 1;
+        let n_y = (height / i64::from(bucket_size)) as i32 + 1;
+        let mut buckets: Vec<Vec<usize>> = vec![Vec::new(); (n_x * n_y) as usize];
+        for (i, p) in points.iter().enumerate() {
+            let bx = (p.x - lo.x) / bucket_size;
+            let by = (p.y - lo.y) / bucket_size;
+            buckets[(bx + by * n_x) as usize].push(i);
+        }
+        NearestIndex {
+            points,
+            metric,
+            origin: lo,
+            bucket_size,
+            n_x,
+            n_y,
+            buckets,
+        }
+    }
 
-            // throw away ties
-            nearest_neighbors.sort_by_key(|(d, _other)| *d);
-            let (distance, nn) = nearest_neighbors[0];
-            let next_distance = nearest_neighbors[1].0;
-            if distance != next_distance {
-                let area = area_around.entry(nn).or_insert(0);
-                *area += 1; // the grid point now adds to the area
+    fn nearest(&self, cell: Point) -> Owner {
+        /// Find which point is nearest the cell, if any one is
+        let bx = (cell.x - self.origin.x).div_euclid(self.bucket_size);
+        let by = (cell.y - self.origin.y).div_euclid(self.bucket_size);
+        let mut best: Option<(i64, Owner)> = None;
+        // skip the rings that don't reach the buckets at all
+        let first_ring = 0
+            .max(-bx)
+            .max(-by)
+            .max(bx - self.n_x + 1)
+            .max(by - self.n_y + 1);
+        let last_ring = bx.max(by).max(self.n_x - 1 - bx).max(self.n_y - 1 - by);
+        for ring in first_ring..=last_ring {
+            // everything in this ring is at least this far away on some axis
+            let bound = self
+                .metric
+                .along_axis(i64::from((ring - 1).max(0) * self.bucket_size));
+            if best.is_some_and(|(d, _)| bound > d) {
+                break;
             }
+            for (rx, ry) in self.ring_buckets(bx, by, ring) {
+                for &i in &self.buckets[(rx + ry * self.n_x) as usize] {
+                    let d = self.metric.distance(cell, self.points[i]);
+                    best = match best {
+                        Some((best_d, _)) if d > best_d => best,
+                        Some((best_d, _)) if d == best_d => Some((d, Owner::Tie)),
+                        _ => Some((d, Owner::Site(i))),
+                    };
+                }
+            }
+        }
+        best.map_or(Owner::Tie, |(_, owner)| owner)
+    }
 
-            // if a point is the nearest neighbor to an edge, it
-            // will be a nearest neighbor out to infinity, so exclude it
-            if y == y_lo || y == y_hi || x == x_lo || x == x_hi {
-                uncontained.insert(nn);
+    fn ring_buckets(&self, x: i32, y: i32, ring: i32) -> Vec<(i32, i32)> {
+        /// The buckets exactly `ring` steps (diagonals included) from (x, y)
+        let (x_lo, x_hi) = ((x - ring).max(0), (x + ring).min(self.n_x - 1));
+        let (y_lo, y_hi) = ((y - ring).max(0), (y + ring).min(self.n_y - 1));
+        let mut ring_buckets = Vec::new();
+        for ry in y_lo..=y_hi {
+            if ry == y - ring || ry == y + ring {
+                ring_buckets.extend((x_lo..=x_hi).map(|rx| (rx, ry)));
+            } else {
+                if x - ring >= 0 {
+                    ring_buckets.push((x - ring, ry));
+                }
+                if ring > 0 && x + ring < self.n_x {
+                    ring_buckets.push((x + ring, ry));
+                }
             }
         }
+        ring_buckets
     }
-    // of all the regions, find the largest one that doesn't go to infinity
-    area_around
-        .iter()
-        .filter(|(p, _area)| !uncontained.contains(p))
-        .map(|(_p, area)| *area)
-        .max()
-        .unwrap()
 }
 
-fn proximal_area(max_distance: i32, points: &[Point]) -> i32 {
-    /// compute the area of the region inside which the sum distance to all points is under a max
+fn bounding_box(points: &[Point]) -> (Point, Point) {
     let x_lo = points.iter().map(|p| p.x).min().unwrap();
     let x_hi = points.iter().map(|p| p.x).max().unwrap();
-
     let y_lo = points.iter().map(|p| p.y).min().unwrap();
     let y_hi = points.iter().map(|p| p.y).max().unwrap();
+    (Point::new(x_lo, y_lo), Point::new(x_hi, y_hi))
+}
+
+fn infinite_regions(points: &[Point], metric: Metric) -> Vec<bool> {
+    /// Work out exactly which points are nearest to infinitely many cells
+    match metric {
+        Metric::Manhattan | Metric::Chebyshev => {
+            let index = NearestIndex::new(points, metric);
+            let mut infinite = vec![false; points.len()];
+            for cell in horizon(points, metric) {
+                if let Owner::Site(i) = index.nearest(cell) {
+                    infinite[i] = true;
+                }
+            }
+            infinite
+        }
+        Metric::EuclideanSquared => {
+            // Regions are convex, and unbounded exactly for the points on the
+            // convex hull; any other point is surrounded by its neighbors.
+            // Repeated points share their cells, so have no region at all.
+            let hull = convex_hull(points);
+            points
+                .iter()
+                .map(|&p| {
+                    points.iter().filter(|&&q| q == p).count() == 1
+                        && (0..hull.len())
+                            .any(|i| on_segment(hull[i], hull[(i + 1) % hull.len()], p))
+                })
+                .collect()
+        }
+    }
+}
 
-    // Since we're using Manattan distance, computing the distance
-    // to all points and summing them at every point is not particularly
-    // efficient.
-    // Better would be to keep track of sorted x and y coordinates of the points.
-    // If we move +1 x, then the distance to all points left increases by 1,
-    // and the distance to all points right decreases by 1, and so the
-    // total distance changes by the difference.
-    // That said, this is simple and seems to run fast enough.
-
-    let mut area: i32 = 0;
-    for y in y_lo..=y_hi {
-        for x in x_lo..=x_hi {
-            let p = Point::new(x, y);
-            let total_distance: i32 = points.iter().map(|other| p.distance(*other)).sum();
-            if total_distance < max_distance {
-                area += 1;
-                if y == y_lo || y == y_hi || x == x_lo || x == x_hi {
-                    panic!("The proximal region might extend past the bounding box of the points");
+fn horizon(points: &[Point], metric: Metric) -> Vec<Point> {
+    /// The cells at the edge of the box in which the Manhattan or Chebyshev
+    /// regions can end. Past the edge, stepping outward adds the same to the
+    /// distance to every point, so the nearest point never changes; a region
+    /// is infinite exactly when its point is nearest some cell on the edge.
+    let (lo, hi) = bounding_box(points);
+    let mut cells = Vec::new();
+    match metric {
+        Metric::Manhattan => {
+            for x in lo.x..=hi.x {
+                cells.push(Point::new(x, lo.y));
+                cells.push(Point::new(x, hi.y));
+            }
+            for y in lo.y..=hi.y {
+                cells.push(Point::new(lo.x, y));
+                cells.push(Point::new(hi.x, y));
+            }
+        }
+        Metric::Chebyshev => {
+            // The same holds in coordinates turned 45 degrees, u = x + y
+            // and v = x - y, stepping diagonally (two at a time in u or v).
+            // Cells only exist where u and v have the same parity, so the
+            // edge is two wide.
+            let (u_lo, u_hi, v_lo, v_hi) = diagonal_box(points);
+            for u in (u_lo - 1)..=(u_hi + 1) {
+                for v in (v_lo - 1)..=(v_hi + 1) {
+                    let edge = u <= u_lo || u >= u_hi || v <= v_lo || v >= v_hi;
+                    if edge && (u - v) % 2 == 0 {
+                        cells.push(Point::new(((u + v) / 2) as i32, ((u - v) / 2) as i32));
+                    }
                 }
             }
         }
+        Metric::EuclideanSquared => panic!("Euclidean regions don't settle down past an edge"),
+    }
+    cells
+}
+
+fn diagonal_box(points: &[Point]) -> (i64, i64, i64, i64) {
+    /// The bounds of the points in u = x + y and v = x - y
+    let u = |p: &Point| i64::from(p.x) + i64::from(p.y);
+    let v = |p: &Point| i64::from(p.x) - i64::from(p.y);
+    (
+        points.iter().map(u).min().unwrap(),
+        points.iter().map(u).max().unwrap(),
+        points.iter().map(v).min().unwrap(),
+        points.iter().map(v).max().unwrap(),
+    )
+}
+
+fn cross(o: Point, a: Point, b: Point) -> i64 {
+    (i64::from(a.x) - i64::from(o.x)) * (i64::from(b.y) - i64::from(o.y))
+        - (i64::from(a.y) - i64::from(o.y)) * (i64::from(b.x) - i64::from(o.x))
+}
+
+fn on_segment(a: Point, b: Point, p: Point) -> bool {
+    cross(a, b, p) == 0
+        && a.x.min(b.x) <= p.x
+        && p.x <= a.x.max(b.x)
+        && a.y.min(b.y) <= p.y
+        && p.y <= a.y.max(b.y)
+}
+
+fn convex_hull(points: &[Point]) -> Vec<Point> {
+    /// The corners of the convex hull, counterclockwise (monotone chain)
+    let mut sorted: Vec<Point> = points.to_vec();
+    sorted.sort_by_key(|p| (p.x, p.y));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+    let mut hull: Vec<Point> = Vec::new();
+    for pass in 0..2 {
+        let start = hull.len();
+        for &p in &sorted {
+            while hull.len() >= start + 2
+                && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0
+            {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        hull.pop(); // it starts the other half
+        if pass == 0 {
+            sorted.reverse();
+        }
+    }
+    hull
+}
+
+fn finite_extent(points: &[Point], metric: Metric, infinite: &[bool]) -> (Point, Point) {
+    /// A box holding every cell of every finite region
+    match metric {
+        Metric::Manhattan => bounding_box(points),
+        Metric::Chebyshev => {
+            let (u_lo, u_hi, v_lo, v_hi) = diagonal_box(points);
+            (
+                Point::new(((u_lo + v_lo) / 2) as i32, ((u_lo - v_hi) / 2) as i32),
+                Point::new(((u_hi + v_hi) / 2) as i32, ((u_hi - v_lo) / 2) as i32),
+            )
+        }
+        Metric::EuclideanSquared => {
+            let (mut lo, mut hi) = bounding_box(points);
+            for (&p, _) in points.iter().zip(infinite).filter(|(_, &inf)| !inf) {
+                let (cell_lo, cell_hi) = euclidean_cell_extent(points, p);
+                lo = Point::new(lo.x.min(cell_lo.x), lo.y.min(cell_lo.y));
+                hi = Point::new(hi.x.max(cell_hi.x), hi.y.max(cell_hi.y));
+            }
+            (lo, hi)
+        }
+    }
+}
+
+fn euclidean_cell(points: &[Point], p: Point) -> Vec<(f64, f64)> {
+    /// The corners of a finite Euclidean region, found by clipping a huge
+    /// square down to the half-plane nearer p than each other point
+    // Corners of the region are centers of circles through three points,
+    // which can't be further away than the cube of the span of the points.
+    let (lo, hi) = bounding_box(points);
+    let span = f64::from((hi.x - lo.x).max(hi.y - lo.y)) + 1.0;
+    let reach = 2.0 * span * span * span;
+    // work relative to p, then shift back at the end
+    let mut polygon: Vec<(f64, f64)> = vec![
+        (-reach, -reach),
+        (reach, -reach),
+        (reach, reach),
+        (-reach, reach),
+    ];
+    for &q in points.iter().filter(|&&q| q != p) {
+        // keep (x, y) with (x, y) . w <= |w|^2 / 2
+        let (wx, wy) = (f64::from(q.x - p.x), f64::from(q.y - p.y));
+        let limit = (wx * wx + wy * wy) / 2.0;
+        let side = |&(x, y): &(f64, f64)| x * wx + y * wy - limit;
+        let mut clipped = Vec::new();
+        for i in 0..polygon.len() {
+            let a = polygon[i];
+            let b = polygon[(i + 1) % polygon.len()];
+            let (sa, sb) = (side(&a), side(&b));
+            if sa <= 0.0 {
+                clipped.push(a);
+            }
+            if (sa < 0.0 && sb > 0.0) || (sa > 0.0 && sb < 0.0) {
+                let t = sa / (sa - sb);
+                clipped.push((a.0 + t * (b.0 - a.0), a.1 + t * (b.1 - a.1)));
+            }
+        }
+        polygon = clipped;
+    }
+    polygon
+        .into_iter()
+        .map(|(x, y)| (x + f64::from(p.x), y + f64::from(p.y)))
+        .collect()
+}
+
+fn euclidean_cell_extent(points: &[Point], p: Point) -> (Point, Point) {
+    /// A box around a finite Euclidean region, padded a cell for rounding
+    let polygon = euclidean_cell(points, p);
+    let (px, py) = (f64::from(p.x), f64::from(p.y));
+    let x_lo = polygon.iter().map(|c| c.0).fold(px, f64::min).floor() as i32 - 1;
+    let x_hi = polygon.iter().map(|c| c.0).fold(px, f64::max).ceil() as i32 + 1;
+    let y_lo = polygon.iter().map(|c| c.1).fold(py, f64::min).floor() as i32 - 1;
+    let y_hi = polygon.iter().map(|c| c.1).fold(py, f64::max).ceil() as i32 + 1;
+    (Point::new(x_lo, y_lo), Point::new(x_hi, y_hi))
+}
+
+fn euclidean_area(points: &[Point], i: usize, index: &NearestIndex) -> usize {
+    /// Count the cells of a finite Euclidean region a row at a time.
+    /// Cells well inside the corners found are certainly nearest the point,
+    /// so only the cells near the ends of each row need checking exactly.
+    let polygon = euclidean_cell(points, points[i]);
+    let (lo, hi) = euclidean_cell_extent(points, points[i]);
+    let mut area = 0;
+    for y in lo.y..=hi.y {
+        // where the row crosses the edges of the (convex) region, with a
+        // little slack so rounding can't make it miss a corner
+        let fy = f64::from(y);
+        let mut x_lo = f64::INFINITY;
+        let mut x_hi = f64::NEG_INFINITY;
+        for j in 0..polygon.len() {
+            let (a, b) = (polygon[j], polygon[(j + 1) % polygon.len()]);
+            if fy < a.1.min(b.1) - 1e-6 || fy > a.1.max(b.1) + 1e-6 {
+                continue;
+            }
+            let crossings = if a.1 == b.1 {
+                [a.0, b.0]
+            } else {
+                let t = ((fy - a.1) / (b.1 - a.1)).clamp(0.0, 1.0);
+                let x = a.0 + t * (b.0 - a.0);
+                [x, x]
+            };
+            for &x in &crossings {
+                x_lo = x_lo.min(x);
+                x_hi = x_hi.max(x);
+            }
+        }
+        if x_lo > x_hi {
+            continue;
+        }
+        let inner_lo = (x_lo + 1.0).ceil() as i32;
+        let inner_hi = (x_hi - 1.0).floor() as i32;
+        if inner_lo <= inner_hi {
+            area += (inner_hi - inner_lo + 1) as usize;
+        }
+        let is_nearest = |x: i32| index.nearest(Point::new(x, y)) == Owner::Site(i);
+        let outer_lo = (x_lo.floor() as i32 - 1).max(lo.x);
+        let outer_hi = (x_hi.ceil() as i32 + 1).min(hi.x);
+        if inner_lo <= inner_hi {
+            area += (outer_lo..inner_lo).filter(|&x| is_nearest(x)).count();
+            area += ((inner_hi + 1)..=outer_hi)
+                .filter(|&x| is_nearest(x))
+                .count();
+        } else {
+            area += (outer_lo..=outer_hi).filter(|&x| is_nearest(x)).count();
+        }
     }
     area
 }
 
+fn region_areas(points: &[Point], metric: Metric) -> Vec<Option<usize>> {
+    /// The number of cells nearest each point, or None for infinite regions
+    let index = NearestIndex::new(points, metric);
+    let infinite = infinite_regions(points, metric);
+    let mut areas = vec![0; points.len()];
+    if metric == Metric::EuclideanSquared {
+        // the regions can be long and thin, so count each separately
+        for i in 0..points.len() {
+            let repeated = points.iter().filter(|&&q| q == points[i]).count() > 1;
+            if !infinite[i] && !repeated {
+                areas[i] = euclidean_area(points, i, &index);
+            }
+        }
+    } else {
+        let (lo, hi) = finite_extent(points, metric, &infinite);
+        for y in lo.y..=hi.y {
+            for x in lo.x..=hi.x {
+                if let Owner::Site(i) = index.nearest(Point::new(x, y)) {
+                    areas[i] += 1;
+                }
+            }
+        }
+    }
+    areas
+        .into_iter()
+        .zip(infinite)
+        .map(|(area, inf)| if inf { None } else { Some(area) })
+        .collect()
+}
+
+fn max_interior_area_with(metric: Metric, points: &[Point]) -> Option<usize> {
+    /// compute the maximum nearest-neighbor area that isn't infinite
+    region_areas(points, metric).into_iter().flatten().max()
+}
+
+/// Total distance from a coordinate to the points' coordinates on one axis
+struct AxisDistances {
+    sorted: Vec<i64>,
+    prefix: Vec<i64>, // prefix[k] is the sum of the first k sorted coordinates
+}
+
+impl AxisDistances {
+    fn new<I: Iterator<Item = i32>>(coords: I) -> AxisDistances {
+        let mut sorted: Vec<i64> = coords.map(i64::from).collect();
+        sorted.sort();
+        let mut prefix = vec![0];
+        for c in &sorted {
+            prefix.push(prefix.last().unwrap() + c);
+        }
+        AxisDistances { sorted, prefix }
+    }
+
+    fn total(&self, c: i64) -> i64 {
+        /// Sum of |c - coordinate| over all the coordinates
+        let n = self.sorted.len();
+        let k = self.sorted.partition_point(|&s| s <= c);
+        let below = c * k as i64 - self.prefix[k];
+        let above = (self.prefix[n] - self.prefix[k]) - c * (n - k) as i64;
+        below + above
+    }
+
+    fn median(&self) -> i64 {
+        self.sorted[self.sorted.len() / 2]
+    }
+
+    fn under(&self, budget: i64) -> (i64, Vec<i64>) {
+        /// The run of coordinates whose total is under the budget, as the
+        /// first coordinate and the totals. The total is convex with its
+        /// minimum at the median, so walk outward from there until it's
+        /// over budget on each side; outside the points it grows by n a step.
+        let median = self.median();
+        if self.total(median) >= budget {
+            return (median, Vec::new());
+        }
+        let mut start = median;
+        while self.total(start - 1) < budget {
+            start -= 1;
+        }
+        let mut totals = Vec::new();
+        let mut c = start;
+        loop {
+            let total = self.total(c);
+            if total >= budget {
+                break;
+            }
+            totals.push(total);
+            c += 1;
+        }
+        (start, totals)
+    }
+}
+
+/// The cells whose total Manhattan distance to all the points is under a
+/// max. Manhattan distance splits into x and y parts, so the total for a
+/// cell is the sum of two precomputed axis totals.
+struct ProximalRegion {
+    max_distance: i64,
+    x_start: i64,
+    x_totals: Vec<i64>,
+    y_start: i64,
+    y_totals: Vec<i64>,
+}
+
+impl ProximalRegion {
+    fn new(max_distance: i64, points: &[Point]) -> ProximalRegion {
+        assert!(!points.is_empty(), "With no points, everywhere is proximal");
+        let xs = AxisDistances::new(points.iter().map(|p| p.x));
+        let ys = AxisDistances::new(points.iter().map(|p| p.y));
+        // a column can only be in the region if it's in at the best row
+        let (x_start, x_totals) = xs.under(max_distance - ys.total(ys.median()));
+        let (y_start, y_totals) = ys.under(max_distance - xs.total(xs.median()));
+        ProximalRegion {
+            max_distance,
+            x_start,
+            x_totals,
+            y_start,
+            y_totals,
+        }
+    }
+
+    fn contains(&self, p: Point) -> bool {
+        let i = i64::from(p.x) - self.x_start;
+        let j = i64::from(p.y) - self.y_start;
+        if i < 0 || j < 0 || i >= self.x_totals.len() as i64 || j >= self.y_totals.len() as i64 {
+            return false;
+        }
+        self.x_totals[i as usize] + self.y_totals[j as usize] < self.max_distance
+    }
+
+    fn extent(&self) -> Option<(Point, Point)> {
+        /// The box the region fits in, which may reach past the points
+        if self.x_totals.is_empty() || self.y_totals.is_empty() {
+            return None;
+        }
+        let coord = |c: i64| i32::try_from(c).expect("Region reaches past the i32 grid");
+        Some((
+            Point::new(coord(self.x_start), coord(self.y_start)),
+            Point::new(
+                coord(self.x_start + self.x_totals.len() as i64 - 1),
+                coord(self.y_start + self.y_totals.len() as i64 - 1),
+            ),
+        ))
+    }
+
+    fn area(&self) -> usize {
+        let (lo, hi) = match self.extent() {
+            Some(extent) => extent,
+            None => return 0,
+        };
+        let mut area = 0;
+        for y in lo.y..=hi.y {
+            area += (lo.x..=hi.x)
+                .filter(|&x| self.contains(Point::new(x, y)))
+                .count();
+        }
+        area
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct MapCell {
+    owner: Owner,
+    site: bool,     // one of the points is right here
+    infinite: bool, // the owner's region goes on forever
+    proximal: bool,
+}
+
+impl Palette for MapCell {
+    fn color(&self) -> Rgb {
+        let base = match self.owner {
+            _ if self.site => return [255, 255, 255],
+            Owner::Tie => [40, 40, 40],
+            Owner::Site(i) => region_color(i),
+        };
+        // dim the regions that don't count, and lighten the proximal ones
+        let base = if self.infinite {
+            base.map(|c| c / 3)
+        } else {
+            base
+        };
+        if self.proximal {
+            base.map(|c| c / 2 + 110)
+        } else {
+            base
+        }
+    }
+}
+
+fn region_color(i: usize) -> Rgb {
+    /// A distinct-ish bright color for each region, stepping the hue by
+    /// the golden angle so neighbors in the list don't look alike
+    let hue = (i as f64 * 137.508) % 360.0 / 60.0;
+    let x = 1.0 - (hue % 2.0 - 1.0).abs();
+    let (r, g, b) = match hue as u32 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+    let shade = |c: f64| (60.0 + 170.0 * c) as u8;
+    [shade(r), shade(g), shade(b)]
+}
+
+fn label(i: usize, n_points: usize) -> String {
+    /// The letters for a point, as many for each point as it takes to give
+    /// them all different ones: just one for up to 26 points, like the puzzle
+    let mut width = 1;
+    let mut n_labels = 26;
+    while n_labels < n_points {
+        width += 1;
+        n_labels *= 26;
+    }
+    let mut letters = vec![b'a'; width];
+    let mut rest = i;
+    for letter in letters.iter_mut().rev() {
+        *letter += (rest % 26) as u8;
+        rest /= 26;
+    }
+    String::from_utf8(letters).unwrap()
+}
+
+/// The nearest-point map over a window around the points, with everything
+/// needed to explain it
+struct AreaMap<'a> {
+    points: &'a [Point],
+    lo: Point,
+    hi: Point,
+    cells: Vec<MapCell>, // row-major over lo..=hi
+    areas: Vec<Option<usize>>,
+    proximal: Option<(i64, usize)>, // max distance and area
+}
+
+impl<'a> AreaMap<'a> {
+    fn new(points: &'a [Point], metric: Metric, max_distance: Option<i64>) -> AreaMap<'a> {
+        /// Map the points, with a cell of margin, and all of the proximal
+        /// region for the max distance if there is one
+        let (lo, hi) = bounding_box(points);
+        let (mut lo, mut hi) = (
+            Point::new(lo.x - 1, lo.y - 1),
+            Point::new(hi.x + 1, hi.y + 1),
+        );
+        let region = max_distance.map(|max_distance| ProximalRegion::new(max_distance, points));
+        if let Some((region_lo, region_hi)) = region.as_ref().and_then(|r| r.extent()) {
+            lo = Point::new(lo.x.min(region_lo.x - 1), lo.y.min(region_lo.y - 1));
+            hi = Point::new(hi.x.max(region_hi.x + 1), hi.y.max(region_hi.y + 1));
+        }
+
+        let index = NearestIndex::new(points, metric);
+        let infinite = infinite_regions(points, metric);
+        let mut cells = Vec::new();
+        for y in lo.y..=hi.y {
+            for x in lo.x..=hi.x {
+                let cell = Point::new(x, y);
+                let owner = index.nearest(cell);
+                cells.push(MapCell {
+                    owner,
+                    site: points.contains(&cell),
+                    infinite: match owner {
+                        Owner::Site(i) => infinite[i],
+                        Owner::Tie => false,
+                    },
+                    proximal: region.as_ref().is_some_and(|r| r.contains(cell)),
+                });
+            }
+        }
+        AreaMap {
+            points,
+            lo,
+            hi,
+            cells,
+            areas: region_areas(points, metric),
+            proximal: max_distance.zip(region.map(|r| r.area())),
+        }
+    }
+
+    fn width(&self) -> usize {
+        (self.hi.x - self.lo.x + 1) as usize
+    }
+
+    fn render(&self) -> String {
+        /// Draw the map like the puzzle does: each point as a capital
+        /// label, the cells nearest it in lowercase, ties as '.', and the
+        /// proximal region (if any) as '#'
+        let n_points = self.points.len();
+        let width = label(0, n_points).len();
+        let mut map = String::new();
+        for row in self.cells.chunks(self.width()) {
+            for cell in row {
+                map.push_str(&match cell.owner {
+                    Owner::Site(i) if cell.site => label(i, n_points).to_ascii_uppercase(),
+                    _ if cell.proximal => "#".repeat(width),
+                    Owner::Site(i) => label(i, n_points),
+                    Owner::Tie => ".".repeat(width),
+                });
+            }
+            map.push('\n');
+        }
+        map
+    }
+
+    fn legend(&self) -> String {
+        /// Which label is which point, and how big its region is, then
+        /// which regions are infinite and so don't count
+        let n_points = self.points.len();
+        let largest = self.areas.iter().flatten().max();
+        let mut legend = String::new();
+        for (i, (p, area)) in self.points.iter().zip(&self.areas).enumerate() {
+            let size = match area {
+                Some(area) if Some(area) == largest => format!("{} (largest)", area),
+                Some(area) => area.to_string(),
+                None => String::from("infinite"),
+            };
+            legend.push_str(&format!(
+                "{} {}, {}: {}\n",
+                label(i, n_points).to_ascii_uppercase(),
+                p.x,
+                p.y,
+                size
+            ));
+        }
+        let infinite: Vec<String> = (0..n_points)
+            .filter(|&i| self.areas[i].is_none())
+            .map(|i| label(i, n_points).to_ascii_uppercase())
+            .collect();
+        if !infinite.is_empty() {
+            legend.push_str(&format!("infinite: {}\n", infinite.join(" ")));
+        }
+        if let Some((max_distance, area)) = self.proximal {
+            legend.push_str(&format!("# within {} in total: {}\n", max_distance, area));
+        }
+        legend
+    }
+
+    fn to_frame(&self, scale: usize) -> Frame {
+        Frame::from_cells(&self.cells, self.width(), scale)
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 struct Point {
     x: i32,
@@ -113,10 +795,6 @@ struct Point {
 }
 
 impl Point {
-    fn distance(self, other: Point) -> i32 {
-        (other.x - self.x).abs() + (other.y - self.y).abs()
-    }
-
     fn new(x: i32, y: i32) -> Point {
         Point { x, y }
     }
@@ -164,7 +842,7 @@ mod tests {
     fn test_distance() {
         struct TestCase {
             input: (Point, Point),
-            output: i32,
+            output: i64,
         }
 
         let cases: Vec<TestCase> = vec![
@@ -187,7 +865,10 @@ mod tests {
         ];
 
         for ref case in &cases[..] {
-            assert_eq!(case.output, Point::distance(case.input.0, case.input.1));
+            assert_eq!(
+                case.output,
+                Metric::Manhattan.distance(case.input.0, case.input.1)
+            );
         }
     }
 
@@ -195,7 +876,7 @@ mod tests {
     fn test_largest_area() {
         struct TestCase {
             input: Vec<Point>,
-            output: i32,
+            output: usize,
         }
 
         let cases: Vec<TestCase> = vec![TestCase {
@@ -211,16 +892,193 @@ mod tests {
         }];
 
         for ref case in &cases[..] {
-            assert_eq!(case.output, max_interior_area(&case.input));
+            assert_eq!(
+                Some(case.output),
+                max_interior_area_with(Metric::Manhattan, &case.input)
+            );
+        }
+    }
+
+    fn example() -> Vec<Point> {
+        vec![
+            Point::new(1, 1),
+            Point::new(1, 6),
+            Point::new(8, 3),
+            Point::new(3, 4),
+            Point::new(5, 5),
+            Point::new(8, 9),
+        ]
+    }
+
+    #[test]
+    fn test_metric_distance() {
+        let (a, b) = (Point::new(-3, 5), Point::new(9, 7));
+        assert_eq!(14, Metric::Manhattan.distance(a, b));
+        assert_eq!(12, Metric::Chebyshev.distance(a, b));
+        assert_eq!(148, Metric::EuclideanSquared.distance(a, b));
+    }
+
+    #[test]
+    fn test_infinite_regions() {
+        let points = example();
+        assert_eq!(
+            vec![true, true, true, false, false, true],
+            infinite_regions(&points, Metric::Manhattan)
+        );
+        assert_eq!(
+            vec![true, true, true, false, false, true],
+            infinite_regions(&points, Metric::EuclideanSquared)
+        );
+        // the middle of three points in a row is infinite for Euclidean
+        // distance, but hemmed in for Chebyshev
+        let row = vec![Point::new(0, 0), Point::new(5, 0), Point::new(10, 0)];
+        assert_eq!(
+            vec![true, true, true],
+            infinite_regions(&row, Metric::EuclideanSquared)
+        );
+        assert_eq!(
+            vec![true, false, true],
+            infinite_regions(&row, Metric::Chebyshev)
+        );
+    }
+
+    #[test]
+    fn test_region_areas() {
+        // counted cell by cell, out past where any finite region reaches
+        struct TestCase {
+            metric: Metric,
+            points: Vec<Point>,
+            output: Vec<Option<usize>>,
+        }
+
+        let plus = vec![
+            Point::new(5, 5),
+            Point::new(5, 0),
+            Point::new(0, 5),
+            Point::new(10, 5),
+            Point::new(5, 10),
+        ];
+        let cases: Vec<TestCase> = vec![
+            TestCase {
+                metric: Metric::Manhattan,
+                points: example(),
+                output: vec![None, None, None, Some(9), Some(17), None],
+            },
+            TestCase {
+                metric: Metric::Chebyshev,
+                points: example(),
+                output: vec![None, None, None, Some(8), Some(10), None],
+            },
+            TestCase {
+                metric: Metric::EuclideanSquared,
+                points: example(),
+                output: vec![None, None, None, Some(10), Some(16), None],
+            },
+            TestCase {
+                metric: Metric::Manhattan,
+                points: plus.clone(),
+                output: vec![Some(25), None, None, None, None],
+            },
+            TestCase {
+                metric: Metric::EuclideanSquared,
+                points: plus,
+                output: vec![Some(25), None, None, None, None],
+            },
+            // two points side by side inside a square of four
+            TestCase {
+                metric: Metric::Chebyshev,
+                points: vec![
+                    Point::new(0, 0),
+                    Point::new(10, 0),
+                    Point::new(0, 10),
+                    Point::new(10, 10),
+                    Point::new(4, 5),
+                    Point::new(6, 5),
+                ],
+                output: vec![None, None, None, None, Some(17), Some(17)],
+            },
+            TestCase {
+                metric: Metric::EuclideanSquared,
+                points: vec![
+                    Point::new(2, 3),
+                    Point::new(7, 1),
+                    Point::new(11, 6),
+                    Point::new(6, 11),
+                    Point::new(1, 9),
+                    Point::new(6, 6),
+                ],
+                output: vec![None, None, None, None, None, Some(25)],
+            },
+        ];
+
+        for case in &cases[..] {
+            assert_eq!(case.output, region_areas(&case.points, case.metric));
         }
     }
 
+    #[test]
+    fn test_render() {
+        let points = example();
+        let map = AreaMap::new(&points, Metric::Manhattan, Some(32));
+        assert_eq!(
+            "aaaaa.cccc\n\
+             aAaaa.cccc\n\
+             aaaddecccc\n\
+             aad###ccCc\n\
+             ..#D###ccc\n\
+             bb###E#ecc\n\
+             bBb###ee..\n\
+             bbb.eeefff\n\
+             bbb.eeffff\n\
+             bbb.ffffFf\n\
+             bbb.ffffff\n",
+            map.render()
+        );
+        assert_eq!(
+            "A 1, 1: infinite\n\
+             B 1, 6: infinite\n\
+             C 8, 3: infinite\n\
+             D 3, 4: 9\n\
+             E 5, 5: 17 (largest)\n\
+             F 8, 9: infinite\n\
+             infinite: A B C F\n\
+             # within 32 in total: 16\n",
+            map.legend()
+        );
+
+        // past 26 points every label takes two letters, so none repeat
+        assert_eq!("z", label(25, 26));
+        assert_eq!("aa", label(0, 27));
+        assert_eq!("ba", label(26, 27));
+        assert_eq!("aaa", label(0, 677));
+        let points: Vec<Point> = (0..27).map(|i| Point::new(i * 2, 0)).collect();
+        let map = AreaMap::new(&points, Metric::Manhattan, None);
+        let render = map.render();
+        let row = render.lines().nth(1).unwrap();
+        assert!(row.starts_with("aaAA..AB..AC"));
+        assert!(row.ends_with("AZ..BAba"));
+    }
+
+    #[test]
+    fn test_to_frame() {
+        let points = example();
+        let map = AreaMap::new(&points, Metric::Manhattan, None);
+        let frame = map.to_frame(2);
+        assert_eq!((20, 22), (frame.width(), frame.height()));
+        assert_eq!([255, 255, 255], frame.pixel(2, 2)); // A
+        assert_eq!([40, 40, 40], frame.pixel(10, 0)); // a tie
+
+        // D's region is finite, A's isn't
+        assert_eq!(region_color(3), frame.pixel(6, 4));
+        assert_ne!(region_color(0), frame.pixel(0, 0));
+    }
+
     #[test]
     fn test_proximate_area() {
         struct TestCase {
             points: Vec<Point>,
-            max_distance: i32,
-            output: i32,
+            max_distance: i64,
+            output: usize,
         }
 
         let cases: Vec<TestCase> = vec![TestCase {
@@ -237,7 +1095,34 @@ mod tests {
         }];
 
         for ref case in &cases[..] {
-            assert_eq!(case.output, proximal_area(case.max_distance, &case.points));
+            assert_eq!(
+                case.output,
+                ProximalRegion::new(case.max_distance, &case.points).area()
+            );
+        }
+    }
+
+    #[test]
+    fn test_proximal_region() {
+        // the region spills well past the box around the points
+        let points = example();
+        for &max_distance in &[0, 1, 32, 100, 1000] {
+            let region = ProximalRegion::new(max_distance, &points);
+            let mut area = 0;
+            for y in -200..200 {
+                for x in -200..200 {
+                    let cell = Point::new(x, y);
+                    let total: i64 = points
+                        .iter()
+                        .map(|&p| Metric::Manhattan.distance(cell, p))
+                        .sum();
+                    assert_eq!(total < max_distance, region.contains(cell));
+                    if region.contains(cell) {
+                        area += 1;
+                    }
+                }
+            }
+            assert_eq!(area, region.area());
         }
     }
 }
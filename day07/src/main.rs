@@ -1,324 +1,1391 @@
 #![allow(unused_doc_comments)]
-use std::collections::{HashMap, HashSet};
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
 use std::env;
+use std::fmt;
 use std::fs;
-use std::iter::FromIterator;
+use std::str::FromStr;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let task = &args[1];
     let filename = &args[2];
 
-    let dependencies: Vec<Dependency> = fs::read_to_string(filename)
-        .unwrap()
-        .split('\n')
-        .filter(|l| !l.is_empty())
-        .map(|l| Dependency::from_str(l))
-        .collect();
-    let steps: HashSet<char> = dependencies
+    let mut dependencies: Vec<Dependency> = Vec::new();
+    for line in fs::read_to_string(filename).unwrap().lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match line.parse::<Dependency>() {
+            Ok(dependency) => dependencies.push(dependency),
+            Err(err) => eprintln!("Skipping {:?}", err),
+        }
+    }
+    let steps: HashSet<String> = dependencies
         .iter()
-        .flat_map(|d| vec![d.first, d.second])
+        .flat_map(|d| vec![d.first.clone(), d.second.clone()])
         .collect();
 
     if task == "sort" {
-        println!(
+        match topological_sort(&steps, &dependencies) {
+            Ok(sorted) => println!("{}", sorted.concat()),
+            Err(cycle) => println!("Circular dependency: {}", cycle),
+        }
+    } else if task == "components" {
+        for component in strongly_connected_components(&steps, &dependencies) {
+            println!("{}", component.join(" "));
+        }
+    } else if task == "time" || task == "schedule" || task == "table" {
+        // time <file> [workers] [durations file] [policy]
+        let n_workers = args
+            .get(3)
+            .map_or(5, |n| n.parse().expect("Couldn't parse number of workers"));
+        let (durations_file, policy) = policy_from_args(&args[4.min(args.len())..]);
+        let timed_steps = steps_from_args(&dependencies, durations_file);
+        let tasks = schedule(n_workers, &timed_steps, &dependencies, policy)
+            .expect("Couldn't schedule the steps");
+        if task == "time" {
+            println!("{}", time_needed(&tasks));
+        } else if task == "table" {
+            print!("{}", render_table(n_workers, &tasks));
+        } else {
+            for task in &tasks {
+                println!("{} {} {} {}", task.worker, task.step, task.start, task.end);
+            }
+        }
+    } else if task == "compare" {
+        // compare <file> [workers] [durations file]
+        let n_workers = args
+            .get(3)
+            .map_or(5, |n| n.parse().expect("Couldn't parse number of workers"));
+        let timed_steps = steps_from_args(&dependencies, args.get(4));
+        for &policy in &POLICIES {
+            let tasks = schedule(n_workers, &timed_steps, &dependencies, policy)
+                .expect("Couldn't schedule the steps");
+            println!("{:<12} {}", policy.name(), time_needed(&tasks));
+        }
+    } else if task == "gantt" {
+        // gantt <file> <out.svg> [workers] [durations file] [policy]
+        let out_file = args.get(3).expect("Need an output file");
+        let n_workers = args
+            .get(4)
+            .map_or(5, |n| n.parse().expect("Couldn't parse number of workers"));
+        let (durations_file, policy) = policy_from_args(&args[5.min(args.len())..]);
+        let timed_steps = steps_from_args(&dependencies, durations_file);
+        let tasks = schedule(n_workers, &timed_steps, &dependencies, policy)
+            .expect("Couldn't schedule the steps");
+        let critical =
+            critical_path(&timed_steps, &dependencies).expect("Couldn't find the critical path");
+        fs::write(out_file, render_gantt(n_workers, &tasks, &critical.path))
+            .expect("Couldn't write chart");
+    } else if task == "critical" {
+        // critical <file> [durations file]
+        let timed_steps = steps_from_args(&dependencies, args.get(3));
+        let critical =
+            critical_path(&timed_steps, &dependencies).expect("Couldn't find the critical path");
+        println!("{} ({})", critical.path.join(" "), critical.length);
+        for timing in &critical.timings {
+            println!(
+                "{} starts {}-{}, slack {}",
+                timing.name,
+                timing.earliest_start,
+                timing.latest_start,
+                timing.slack()
+            );
+        }
+    } else if task == "dot" {
+        // dot <file> [durations] [order] [critical] [durations file]
+        let mut options = DotOptions::default();
+        let mut durations_file = None;
+        for arg in &args[3..] {
+            match arg.as_str() {
+                "durations" => options.durations = true,
+                "order" => options.order = true,
+                "critical" => options.critical = true,
+                _ if arg.starts_with('-') => panic!("Don't know the option '{}'", arg),
+                _ if durations_file.is_some() => panic!("Only one durations file, not '{}'", arg),
+                _ => durations_file = Some(arg),
+            }
+        }
+        let timed_steps = if options.durations || options.critical {
+            steps_from_args(&dependencies, durations_file)
+        } else {
+            Vec::new()
+        };
+        print!(
             "{}",
-            topological_sort(&steps, &dependencies)
-                .iter()
-                .collect::<String>()
+            to_dot(&dependencies, &timed_steps, &options).expect("Couldn't draw the graph")
         );
-    } else if task == "time" {
-        let timed_steps: Vec<Step> = steps.iter().map(|step| Step::new(*step, 60)).collect();
-        println!("{}", time_needed(5, &timed_steps, &dependencies))
     } else {
         panic!("Don't know how to '{}'", task);
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+fn steps_from_args(dependencies: &[Dependency], durations_file: Option<&String>) -> Vec<Step> {
+    /// The steps with durations from the file if there is one, or else
+    /// the puzzle's durations
+    let durations = durations_file.map(|durations_file| {
+        let text = fs::read_to_string(durations_file).unwrap_or_else(|err| {
+            panic!("Couldn't read durations file '{}': {}", durations_file, err)
+        });
+        read_durations(&text).expect("Couldn't parse durations")
+    });
+    timed_steps(dependencies, durations.as_ref(), 60).expect("Couldn't time the steps")
+}
+
+fn policy_from_args(args: &[String]) -> (Option<&String>, Policy) {
+    /// Pick the policy out of the remaining arguments, leaving the
+    /// durations file if there is one. Only one argument can be the file,
+    /// so any other that isn't a policy is a mistake.
+    let mut durations_file = None;
+    let mut policy = Policy::Alphabetical;
+    for arg in args {
+        match Policy::from_name(arg) {
+            Some(named) => policy = named,
+            None => match durations_file {
+                None => durations_file = Some(arg),
+                Some(file) => panic!(
+                    "Neither '{}' nor '{}' is a policy, and only one can be the durations file",
+                    file, arg
+                ),
+            },
+        }
+    }
+    (durations_file, policy)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct ParseError(String); // the line that couldn't be parsed
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 struct Dependency {
-    first: char,
-    second: char,
+    first: String,
+    second: String,
 }
 
 impl Dependency {
-    fn new(first: char, second: char) -> Dependency {
-        Dependency { first, second }
+    fn new(first: &str, second: &str) -> Dependency {
+        Dependency {
+            first: String::from(first),
+            second: String::from(second),
+        }
     }
+}
 
-    fn from_str(dependency: &str) -> Dependency {
-        // assumes step reads "Step A must be finished before step B can begin."
-        let chars: Vec<char> = dependency.chars().collect();
-        let first = chars[5];
-        let second = chars[36];
-        Dependency::new(first, second)
+impl FromStr for Dependency {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // "Step A must be finished before step B can begin.", where the
+        // step names can be any words
+        let words: Vec<&str> = s.split_whitespace().collect();
+        match words[..] {
+            ["Step", first, "must", "be", "finished", "before", "step", second, "can", "begin."] => {
+                Ok(Dependency::new(first, second))
+            }
+            _ => Err(ParseError(String::from(s))),
+        }
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 struct Step {
-    id: char,
-    time_left: i32,
+    name: String,
+    duration: i64,
 }
 
 impl Step {
-    fn new(id: char, extra_time: i32) -> Step {
-        let id_time = ((id as u32) - ('A' as u32) + 1u32) as i32;
+    fn new(name: &str, duration: i64) -> Step {
         Step {
-            id,
-            time_left: id_time + extra_time,
+            name: String::from(name),
+            duration,
         }
     }
 
-    fn tick(&mut self) {
-        self.time_left -= 1;
+    fn lettered(name: &str, extra_time: i64) -> Option<Step> {
+        /// The puzzle's durations: a single capital letter takes its
+        /// place in the alphabet (A = 1) plus some extra time
+        match name.as_bytes() {
+            &[letter @ b'A'..=b'Z'] => {
+                Some(Step::new(name, i64::from(letter - b'A' + 1) + extra_time))
+            }
+            _ => None,
+        }
     }
+}
 
-    fn is_done(self) -> bool {
-        self.time_left <= 0
+fn read_durations(s: &str) -> Result<HashMap<String, i64>, ParseError> {
+    /// Read lines of "<step> <duration>", skipping blank lines
+    let mut durations = HashMap::new();
+    for line in s.lines().filter(|line| !line.trim().is_empty()) {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            [name, duration] => {
+                let duration: i64 = duration
+                    .parse()
+                    .map_err(|_| ParseError(String::from(line)))?;
+                if duration < 0 {
+                    return Err(ParseError(String::from(line)));
+                }
+                durations.insert(String::from(name), duration);
+            }
+            _ => return Err(ParseError(String::from(line))),
+        }
     }
+    Ok(durations)
 }
 
-fn topological_sort(nodes: &HashSet<char>, dependencies: &[Dependency]) -> Vec<char> {
-    /// Given a set of nodes and edges, return a topologically sorted vec of nodes
-    // I could probably clean up this interface to match the problem 2 one. Ah well.
-    // Kahn's Algorithm
-    let mut sorted: Vec<char> = Vec::new();
-    let mut remaining_deps: HashSet<Dependency> = HashSet::from_iter(dependencies.iter().cloned());
+fn timed_steps(
+    dependencies: &[Dependency],
+    durations: Option<&HashMap<String, i64>>,
+    extra_time: i64,
+) -> Result<Vec<Step>, ScheduleError> {
+    /// Give every step named in the dependencies (or the durations) its
+    /// duration, from the durations if given or else the puzzle's rule
+    let mut names: BTreeSet<&str> = BTreeSet::new();
+    for dependency in dependencies {
+        names.insert(&dependency.first);
+        names.insert(&dependency.second);
+    }
+    if let Some(durations) = durations {
+        names.extend(durations.keys().map(|name| name.as_str()));
+    }
+    names
+        .into_iter()
+        .map(|name| {
+            let step = match durations {
+                Some(durations) => durations
+                    .get(name)
+                    .map(|&duration| Step::new(name, duration)),
+                None => Step::lettered(name, extra_time),
+            };
+            step.ok_or_else(|| ScheduleError::NoDuration(String::from(name)))
+        })
+        .collect()
+}
 
-    loop {
-        let mut start_nodes: HashSet<char> = nodes.clone();
-        for edge in &remaining_deps {
-            start_nodes.remove(&edge.second);
+/// The steps and their dependencies as lists of step indices. The steps
+/// are sorted by name, so going by index is going alphabetically.
+struct Graph {
+    names: Vec<String>,
+    prereqs: Vec<Vec<usize>>,
+    dependents: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    fn new<'a, I: IntoIterator<Item = &'a str>>(nodes: I, dependencies: &[Dependency]) -> Graph {
+        /// Steps named only in the dependencies are included too
+        let mut names: BTreeSet<&str> = nodes.into_iter().collect();
+        for dependency in dependencies {
+            names.insert(&dependency.first);
+            names.insert(&dependency.second);
         }
-        for used_node in &sorted {
-            start_nodes.remove(&used_node);
+        let names: Vec<String> = names.into_iter().map(String::from).collect();
+        let mut prereqs: Vec<Vec<usize>> = vec![Vec::new(); names.len()];
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); names.len()];
+        let index = |name: &str| names.binary_search_by(|n| n.as_str().cmp(name)).unwrap();
+        for dependency in dependencies {
+            let (first, second) = (index(&dependency.first), index(&dependency.second));
+            prereqs[second].push(first);
+            dependents[first].push(second);
         }
+        Graph {
+            names,
+            prereqs,
+            dependents,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.names.len()
+    }
 
-        let node = *start_nodes.iter().min().unwrap();
-        start_nodes.remove(&node);
-        sorted.push(node);
+    fn sorted(&self) -> Result<Vec<usize>, Cycle> {
+        /// The steps in dependency order, alphabetically first when there's a choice
+        // Kahn's Algorithm
+        let mut waiting: Vec<usize> = self.prereqs.iter().map(|p| p.len()).collect();
+        let mut available: BTreeSet<usize> = (0..self.len()).filter(|&i| waiting[i] == 0).collect();
+        let mut done: Vec<bool> = vec![false; self.len()];
+        let mut sorted: Vec<usize> = Vec::new();
 
-        let deps_satisfied: Vec<Dependency> = remaining_deps
+        while let Some(node) = available.pop_first() {
+            done[node] = true;
+            sorted.push(node);
+            for &next in &self.dependents[node] {
+                waiting[next] -= 1;
+                if waiting[next] == 0 {
+                    available.insert(next);
+                }
+            }
+        }
+        if sorted.len() < self.len() {
+            return Err(self.find_cycle(&done));
+        }
+        Ok(sorted)
+    }
+
+    fn durations(&self, steps: &[Step]) -> Result<Vec<i64>, ScheduleError> {
+        /// How long each step takes, by index
+        let durations: HashMap<&str, i64> = steps
             .iter()
-            .filter(|e| e.first == node)
-            .cloned()
+            .map(|step| (step.name.as_str(), step.duration))
             .collect();
-        for edge in deps_satisfied {
-            remaining_deps.remove(&edge);
-        }
+        self.names
+            .iter()
+            .map(|name| {
+                durations
+                    .get(name.as_str())
+                    .cloned()
+                    .ok_or_else(|| ScheduleError::NoDuration(name.clone()))
+            })
+            .collect()
+    }
 
-        if sorted.len() == nodes.len() {
-            break;
-        }
+    fn find_cycle(&self, done: &[bool]) -> Cycle {
+        /// Every step not done is waiting on some other step not done, so
+        /// following those back from any of them has to go round in a loop
+        let start = done.iter().position(|&d| !d).unwrap();
+        let mut path: Vec<usize> = Vec::new();
+        let mut position: HashMap<usize, usize> = HashMap::new();
+        let mut step = start;
+        let loop_start = loop {
+            position.insert(step, path.len());
+            path.push(step);
+            step = *self.prereqs[step]
+                .iter()
+                .filter(|&&prereq| !done[prereq])
+                .min()
+                .unwrap();
+            if let Some(&i) = position.get(&step) {
+                break i;
+            }
+        };
+        // the path went backwards along the dependencies
+        let mut cycle: Vec<usize> = path[loop_start..].iter().rev().cloned().collect();
+        let first = (0..cycle.len()).min_by_key(|&i| cycle[i]).unwrap();
+        cycle.rotate_left(first);
+        Cycle(cycle.into_iter().map(|i| self.names[i].clone()).collect())
     }
-    if !remaining_deps.is_empty() {
-        panic!("circular dependency");
+}
+
+/// Steps that each have to be finished before the next one, with the last
+/// before the first, so none of them can ever start
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Cycle(Vec<String>);
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} -> {}", self.0.join(" -> "), self.0[0])
     }
-    sorted
 }
 
-fn steps_available(
-    steps: &HashMap<char, Step>,
-    dependencies: &HashMap<char, Vec<char>>,
-) -> Vec<char> {
-    /// Given a map of id to Step, and a map of id to vec of dependencies, return unblocked steps
-    let mut available: Vec<char> = Vec::new();
-    for &step in steps.values() {
-        if step.is_done() {
+fn topological_sort(
+    nodes: &HashSet<String>,
+    dependencies: &[Dependency],
+) -> Result<Vec<String>, Cycle> {
+    /// Given a set of nodes and edges, return a topologically sorted vec of nodes,
+    /// taking the alphabetically first when there's a choice
+    let graph = Graph::new(nodes.iter().map(|node| node.as_str()), dependencies);
+    Ok(graph
+        .sorted()?
+        .into_iter()
+        .map(|node| graph.names[node].clone())
+        .collect())
+}
+
+fn strongly_connected_components(
+    nodes: &HashSet<String>,
+    dependencies: &[Dependency],
+) -> Vec<Vec<String>> {
+    /// Group the steps into sets that all depend on each other, in an
+    /// order where every prerequisite's set comes before its dependents'.
+    /// Without cycles, every step is its own set.
+    // Tarjan's algorithm, with an explicit stack so deep chains of
+    // dependencies can't overflow
+    let graph = Graph::new(nodes.iter().map(|node| node.as_str()), dependencies);
+    let n = graph.len();
+    let mut order: Vec<Option<usize>> = vec![None; n]; // when each step was first reached
+    let mut low: Vec<usize> = vec![0; n];
+    let mut on_stack: Vec<bool> = vec![false; n];
+    let mut stack: Vec<usize> = Vec::new();
+    let mut components: Vec<Vec<usize>> = Vec::new();
+    let mut counter = 0;
+
+    for root in 0..n {
+        if order[root].is_some() {
             continue;
         }
-        if dependencies
-            .get(&step.id)
-            .unwrap_or(&vec![])
-            .iter()
-            .all(|prereq| steps[prereq].is_done())
-        {
-            available.push(step.id);
+        let mut calls: Vec<(usize, usize)> = vec![(root, 0)]; // step, next dependent to visit
+        while let Some(&mut (node, ref mut next)) = calls.last_mut() {
+            if *next == 0 && order[node].is_none() {
+                order[node] = Some(counter);
+                low[node] = counter;
+                counter += 1;
+                stack.push(node);
+                on_stack[node] = true;
+            }
+            if let Some(&child) = graph.dependents[node].get(*next) {
+                *next += 1;
+                match order[child] {
+                    None => calls.push((child, 0)),
+                    Some(child_order) if on_stack[child] => low[node] = low[node].min(child_order),
+                    Some(_) => (),
+                }
+                continue;
+            }
+            calls.pop();
+            if let Some(&(parent, _)) = calls.last() {
+                low[parent] = low[parent].min(low[node]);
+            }
+            if Some(low[node]) == order[node] {
+                let mut component = Vec::new();
+                loop {
+                    let member = stack.pop().unwrap();
+                    on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                component.sort();
+                components.push(component);
+            }
+        }
+    }
+    // Tarjan's finds dependents' sets before their prerequisites', so sort
+    // the sets again, going alphabetically by their first step when there's
+    // a choice, like topological_sort does
+    components.sort();
+    let mut component_of: Vec<usize> = vec![0; n];
+    for (c, component) in components.iter().enumerate() {
+        for &member in component {
+            component_of[member] = c;
+        }
+    }
+    let mut waiting: Vec<usize> = vec![0; components.len()];
+    for (node, dependents) in graph.dependents.iter().enumerate() {
+        for &next in dependents {
+            if component_of[node] != component_of[next] {
+                waiting[component_of[next]] += 1;
+            }
+        }
+    }
+    let mut available: BTreeSet<usize> =
+        (0..components.len()).filter(|&c| waiting[c] == 0).collect();
+    let mut sorted: Vec<Vec<String>> = Vec::new();
+    while let Some(c) = available.pop_first() {
+        for &member in &components[c] {
+            for &next in &graph.dependents[member] {
+                if component_of[next] != c {
+                    waiting[component_of[next]] -= 1;
+                    if waiting[component_of[next]] == 0 {
+                        available.insert(component_of[next]);
+                    }
+                }
+            }
         }
+        sorted.push(
+            components[c]
+                .iter()
+                .map(|&member| graph.names[member].clone())
+                .collect(),
+        );
     }
-    available
+    sorted
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum ScheduleError {
+    NoDuration(String), // a step with no duration given
+    NoWorkers,
+    Cycle(Cycle), // so some steps could never start
 }
 
-fn time_needed(n_workers: usize, all_steps: &[Step], dependency_edges: &[Dependency]) -> i64 {
-    /// Given n workers, Step nodes, and dependency edges, how long does everything take?
-    // I could probably clean up the data structures here. Like,
-    // the HashMap indexed by IDs, mapping those IDs to structs
-    // containing those IDs could probably do with some refactoring.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Policy {
+    Alphabetical,   // the puzzle's rule
+    LongestFirst,   // the longest available step first
+    MostDependents, // the step most other steps are directly waiting on first
+    CriticalPath,   // the step with the longest chain left after it first
+}
+
+const POLICIES: [Policy; 4] = [
+    Policy::Alphabetical,
+    Policy::LongestFirst,
+    Policy::MostDependents,
+    Policy::CriticalPath,
+];
 
-    // map step id to the Step struct
-    let mut steps: HashMap<char, Step> = HashMap::new();
-    for step in all_steps.iter().cloned() {
-        steps.insert(step.id, step);
+impl Policy {
+    fn name(self) -> &'static str {
+        match self {
+            Policy::Alphabetical => "alphabetical",
+            Policy::LongestFirst => "longest",
+            Policy::MostDependents => "dependents",
+            Policy::CriticalPath => "critical",
+        }
     }
 
-    // map step id to the ids of other steps its dependent on
-    let mut dependencies: HashMap<char, Vec<char>> = HashMap::new();
-    for &edge in dependency_edges {
-        let mut d = dependencies
-            .entry(edge.second)
-            .or_insert_with(|| [].to_vec());
-        d.push(edge.first);
+    fn from_name(name: &str) -> Option<Policy> {
+        POLICIES
+            .iter()
+            .cloned()
+            .find(|policy| policy.name() == name)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Task {
+    worker: usize,
+    step: String,
+    start: i64,
+    end: i64,
+}
+
+fn schedule(
+    n_workers: usize,
+    steps: &[Step],
+    dependencies: &[Dependency],
+    policy: Policy,
+) -> Result<Vec<Task>, ScheduleError> {
+    /// Work out who does each step when: whenever workers are free, the
+    /// lowest numbered ones take the available steps in the order the
+    /// policy prefers, alphabetical among equals
+    if n_workers == 0 {
+        return Err(ScheduleError::NoWorkers);
     }
+    let graph = Graph::new(steps.iter().map(|step| step.name.as_str()), dependencies);
+    let duration = graph.durations(steps)?;
+
+    // lower goes first
+    let priority: Vec<i64> = match policy {
+        Policy::Alphabetical => vec![0; graph.len()],
+        Policy::LongestFirst => duration.iter().map(|&d| -d).collect(),
+        Policy::MostDependents => graph
+            .dependents
+            .iter()
+            .map(|next| -(next.len() as i64))
+            .collect(),
+        Policy::CriticalPath => critical_path(steps, dependencies)?
+            .timings
+            .iter()
+            .map(|timing| timing.latest_start)
+            .collect(),
+    };
+
+    // how many prerequisites each step is waiting on
+    let mut waiting: Vec<usize> = graph.prereqs.iter().map(|p| p.len()).collect();
 
-    // an array keeps track of what's being worked on
-    let mut work: Vec<Option<char>> = vec![None; n_workers];
+    // step indices are in alphabetical order, so the sets hand out the
+    // preferred available step to the lowest numbered free worker
+    let mut available: BTreeSet<(i64, usize)> = (0..graph.len())
+        .filter(|&i| waiting[i] == 0)
+        .map(|i| (priority[i], i))
+        .collect();
+    let mut free: BTreeSet<usize> = (0..n_workers).collect();
+    let mut running: BinaryHeap<Reverse<(i64, usize, usize)>> = BinaryHeap::new(); // end, worker, step
+    let mut tasks: Vec<Task> = Vec::new();
     let mut t: i64 = 0;
-    while !steps.values().all(|s| s.is_done()) {
-        // figure out what can be worked on, and only one worker can work on a thing at a time
-        let mut avail: Vec<char> = steps_available(&steps, &dependencies)
+    loop {
+        while let (Some(&worker), Some(&(p, step))) = (free.iter().next(), available.iter().next())
+        {
+            free.remove(&worker);
+            available.remove(&(p, step));
+            let end = t + duration[step];
+            tasks.push(Task {
+                worker,
+                step: graph.names[step].clone(),
+                start: t,
+                end,
+            });
+            running.push(Reverse((end, worker, step)));
+        }
+
+        // jump ahead to when the next steps finish
+        t = match running.peek() {
+            Some(&Reverse((end, _, _))) => end,
+            None => break,
+        };
+        while let Some(&Reverse((end, worker, step))) = running.peek() {
+            if end > t {
+                break;
+            }
+            running.pop();
+            free.insert(worker);
+            for &next in &graph.dependents[step] {
+                waiting[next] -= 1;
+                if waiting[next] == 0 {
+                    available.insert((priority[next], next));
+                }
+            }
+        }
+    }
+
+    if tasks.len() < graph.len() {
+        let started: HashSet<&str> = tasks.iter().map(|task| task.step.as_str()).collect();
+        let done: Vec<bool> = graph
+            .names
             .iter()
-            .filter(|&s| !work.contains(&Some(*s)))
-            .cloned()
+            .map(|name| started.contains(name.as_str()))
             .collect();
-        // work on the first alphabetically available
-        avail.sort();
-        avail.reverse();
-
-        t += 1;
-
-        for i in 0..work.len() {
-            // clear out anything done
-            match work[i] {
-                None => (),
-                Some(s) => {
-                    if steps[&s].is_done() {
-                        work[i] = None;
-                    }
-                }
-            };
+        return Err(ScheduleError::Cycle(graph.find_cycle(&done)));
+    }
+    Ok(tasks)
+}
 
-            // fill up any free work
-            match work[i] {
-                None => work[i] = avail.pop(),
-                Some(_) => (),
-            }
+fn time_needed(tasks: &[Task]) -> i64 {
+    /// How long it takes until the last task is done
+    tasks.iter().map(|task| task.end).max().unwrap_or(0)
+}
 
-            // tick time on anything being worked on
-            match work[i] {
-                None => (),
-                Some(s) => {
-                    steps.get_mut(&s).unwrap().tick();
-                }
+fn render_table(n_workers: usize, tasks: &[Task]) -> String {
+    /// Lay out the schedule second by second like the puzzle does, with
+    /// what each worker is doing and the steps done so far
+    let end = time_needed(tasks);
+    let width = tasks
+        .iter()
+        .map(|task| task.step.len())
+        .max()
+        .unwrap_or(0)
+        .max("Worker 10".len());
+    // single letter steps run together, like the puzzle, but longer
+    // names need separating
+    let separator = if tasks.iter().all(|task| task.step.len() == 1) {
+        ""
+    } else {
+        ","
+    };
+
+    let mut table = String::from("Second");
+    for worker in 0..n_workers {
+        table.push_str(&format!(
+            "   {:<width$}",
+            format!("Worker {}", worker + 1),
+            width = width
+        ));
+    }
+    table.push_str("   Done\n");
+    // each worker's tasks in order, and how far through them it is
+    let mut by_worker: Vec<Vec<&Task>> = vec![Vec::new(); n_workers];
+    for task in tasks {
+        by_worker[task.worker].push(task);
+    }
+    for worker_tasks in &mut by_worker {
+        worker_tasks.sort_by_key(|task| task.start);
+    }
+    let mut current: Vec<usize> = vec![0; n_workers];
+    let mut finished: Vec<&Task> = tasks.iter().collect();
+    finished.sort_by_key(|task| (task.end, task.worker));
+    let mut n_done = 0;
+    let mut done = String::new();
+    for t in 0..=end {
+        let mut row = format!("{:>6}", t);
+        for worker in 0..n_workers {
+            let worker_tasks = &by_worker[worker];
+            while current[worker] < worker_tasks.len() && worker_tasks[current[worker]].end <= t {
+                current[worker] += 1;
             }
+            let step = worker_tasks
+                .get(current[worker])
+                .filter(|task| task.start <= t)
+                .map_or(".", |task| task.step.as_str());
+            row.push_str(&format!("   {:<width$}", step, width = width));
         }
+        while n_done < finished.len() && finished[n_done].end <= t {
+            if n_done > 0 {
+                done.push_str(separator);
+            }
+            done.push_str(&finished[n_done].step);
+            n_done += 1;
+        }
+        row.push_str("   ");
+        row.push_str(&done);
+        table.push_str(row.trim_end());
+        table.push('\n');
     }
-    t
+    table
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct StepTiming {
+    name: String,
+    duration: i64,
+    earliest_start: i64,
+    latest_start: i64, // without holding up the whole job
+}
+
+impl StepTiming {
+    fn slack(&self) -> i64 {
+        self.latest_start - self.earliest_start
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct CriticalPath {
+    length: i64,              // the least time the job can take, with enough workers
+    path: Vec<String>,        // a chain of steps with no slack that takes that long
+    timings: Vec<StepTiming>, // for every step, by name
+}
+
+fn critical_path(
+    steps: &[Step],
+    dependencies: &[Dependency],
+) -> Result<CriticalPath, ScheduleError> {
+    /// Work out how early each step can start with unlimited workers, how
+    /// late it can start without delaying the end, and the chain of steps
+    /// that can't be delayed at all
+    let graph = Graph::new(steps.iter().map(|step| step.name.as_str()), dependencies);
+    let duration = graph.durations(steps)?;
+    let order = graph.sorted().map_err(ScheduleError::Cycle)?;
+
+    // forward for the earliest starts, then backward for the latest
+    let mut earliest: Vec<i64> = vec![0; graph.len()];
+    for &step in &order {
+        earliest[step] = graph.prereqs[step]
+            .iter()
+            .map(|&prereq| earliest[prereq] + duration[prereq])
+            .max()
+            .unwrap_or(0);
+    }
+    let length = (0..graph.len())
+        .map(|step| earliest[step] + duration[step])
+        .max()
+        .unwrap_or(0);
+    let mut latest: Vec<i64> = vec![0; graph.len()];
+    for &step in order.iter().rev() {
+        latest[step] = graph.dependents[step]
+            .iter()
+            .map(|&next| latest[next])
+            .min()
+            .unwrap_or(length)
+            - duration[step];
+    }
+
+    // follow steps with no slack from the start, alphabetically first
+    // when there's a choice
+    let mut path: Vec<String> = Vec::new();
+    let mut current =
+        (0..graph.len()).find(|&step| graph.prereqs[step].is_empty() && latest[step] == 0);
+    while let Some(step) = current {
+        path.push(graph.names[step].clone());
+        let finish = earliest[step] + duration[step];
+        current = graph.dependents[step]
+            .iter()
+            .filter(|&&next| latest[next] == earliest[next] && earliest[next] == finish)
+            .min()
+            .cloned();
+    }
+
+    let timings = (0..graph.len())
+        .map(|step| StepTiming {
+            name: graph.names[step].clone(),
+            duration: duration[step],
+            earliest_start: earliest[step],
+            latest_start: latest[step],
+        })
+        .collect();
+    Ok(CriticalPath {
+        length,
+        path,
+        timings,
+    })
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_gantt(n_workers: usize, tasks: &[Task], critical: &[String]) -> String {
+    /// Draw the schedule as an SVG Gantt chart, a row per worker, with the
+    /// steps on the critical path picked out
+    const CHART_WIDTH: f64 = 1000.0;
+    const ROW_HEIGHT: usize = 30;
+    const LABEL_WIDTH: usize = 80;
+    let end = time_needed(tasks).max(1);
+    let scale = CHART_WIDTH / end as f64;
+    let height = ROW_HEIGHT * (n_workers + 1);
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"monospace\" font-size=\"12\">\n",
+        LABEL_WIDTH + CHART_WIDTH as usize + 10,
+        height
+    );
+    for worker in 0..n_workers {
+        svg.push_str(&format!(
+            "  <text x=\"4\" y=\"{}\">Worker {}</text>\n",
+            ROW_HEIGHT * worker + 19,
+            worker + 1
+        ));
+    }
+    for task in tasks {
+        let x = LABEL_WIDTH as f64 + task.start as f64 * scale;
+        let width = (task.end - task.start) as f64 * scale;
+        let fill = if critical.contains(&task.step) {
+            "#e0603c"
+        } else {
+            "#6c9bd2"
+        };
+        svg.push_str(&format!(
+            "  <rect x=\"{:.1}\" y=\"{}\" width=\"{:.1}\" height=\"{}\" fill=\"{}\" stroke=\"white\"><title>{} {}-{}</title></rect>\n",
+            x,
+            ROW_HEIGHT * task.worker + 4,
+            width,
+            ROW_HEIGHT - 8,
+            fill,
+            xml_escape(&task.step),
+            task.start,
+            task.end
+        ));
+        // only label the bars with room for it
+        if width >= 8.0 * task.step.len() as f64 {
+            svg.push_str(&format!(
+                "  <text x=\"{:.1}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+                x + width / 2.0,
+                ROW_HEIGHT * task.worker + 19,
+                xml_escape(&task.step)
+            ));
+        }
+    }
+    // the time axis
+    let axis_y = ROW_HEIGHT * n_workers + 4;
+    svg.push_str(&format!(
+        "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\"/>\n",
+        LABEL_WIDTH,
+        axis_y,
+        LABEL_WIDTH + CHART_WIDTH as usize,
+        axis_y
+    ));
+    for tick in 0..=10 {
+        let t = end * tick / 10;
+        svg.push_str(&format!(
+            "  <text x=\"{:.1}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+            LABEL_WIDTH as f64 + t as f64 * scale,
+            axis_y + 16,
+            t
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[derive(Debug, Default)]
+struct DotOptions {
+    durations: bool, // label each step with how long it takes
+    order: bool,     // number the steps in topological order
+    critical: bool,  // highlight the critical path
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn dot_quote(s: &str) -> String {
+    format!("\"{}\"", dot_escape(s))
+}
+
+fn to_dot(
+    dependencies: &[Dependency],
+    steps: &[Step],
+    options: &DotOptions,
+) -> Result<String, ScheduleError> {
+    /// Write the dependencies as a Graphviz graph. The steps only need
+    /// durations for the durations and critical path options.
+    let graph = Graph::new(steps.iter().map(|step| step.name.as_str()), dependencies);
+    let durations = if options.durations {
+        Some(graph.durations(steps)?)
+    } else {
+        None
+    };
+    let order: Vec<usize> = if options.order {
+        graph.sorted().map_err(ScheduleError::Cycle)?
+    } else {
+        Vec::new()
+    };
+    // where each step comes in the order, if it's wanted
+    let mut positions: Vec<Option<usize>> = vec![None; graph.names.len()];
+    for (position, &step) in order.iter().enumerate() {
+        positions[step] = Some(position);
+    }
+    let critical: Vec<String> = if options.critical {
+        critical_path(steps, dependencies)?.path
+    } else {
+        Vec::new()
+    };
+    let critical_steps: HashSet<&str> = critical.iter().map(|name| name.as_str()).collect();
+    // an edge is on the critical path if it joins consecutive steps of it
+    let critical_edges: HashSet<(&str, &str)> = critical
+        .windows(2)
+        .map(|pair| (pair[0].as_str(), pair[1].as_str()))
+        .collect();
+
+    let mut dot = String::from("digraph steps {\n    rankdir=LR;\n    node [shape=box];\n");
+    for (step, name) in graph.names.iter().enumerate() {
+        // escape each line of the label so the \n between them survives
+        let mut label = vec![match positions[step] {
+            Some(position) => format!("{}. {}", position + 1, name),
+            None => name.clone(),
+        }];
+        if let Some(durations) = &durations {
+            label.push(format!("{}s", durations[step]));
+        }
+        let label: Vec<String> = label.iter().map(|line| dot_escape(line)).collect();
+        let style = if critical_steps.contains(name.as_str()) {
+            ", color=red, penwidth=2"
+        } else {
+            ""
+        };
+        dot.push_str(&format!(
+            "    {} [label=\"{}\"{}];\n",
+            dot_quote(name),
+            label.join("\\n"),
+            style
+        ));
+    }
+    for dependency in dependencies {
+        let on_path =
+            critical_edges.contains(&(dependency.first.as_str(), dependency.second.as_str()));
+        dot.push_str(&format!(
+            "    {} -> {}{};\n",
+            dot_quote(&dependency.first),
+            dot_quote(&dependency.second),
+            if on_path {
+                " [color=red, penwidth=2]"
+            } else {
+                ""
+            }
+        ));
+    }
+    dot.push_str("}\n");
+    Ok(dot)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn example() -> Vec<Dependency> {
+        vec![
+            Dependency::new("C", "A"),
+            Dependency::new("C", "F"),
+            Dependency::new("A", "B"),
+            Dependency::new("A", "D"),
+            Dependency::new("B", "E"),
+            Dependency::new("D", "E"),
+            Dependency::new("F", "E"),
+        ]
+    }
+
+    fn names(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|&name| String::from(name)).collect()
+    }
+
     #[test]
     fn test_parse() {
         struct TestCase {
             input: String,
-            output: Dependency,
+            output: Result<Dependency, ParseError>,
         }
 
         let cases: Vec<TestCase> = vec![
             TestCase {
                 input: String::from("Step A must be finished before step B can begin."),
-                output: Dependency::new('A', 'B'),
+                output: Ok(Dependency::new("A", "B")),
             },
             TestCase {
                 input: String::from("Step D must be finished before step Z can begin."),
-                output: Dependency::new('D', 'Z'),
+                output: Ok(Dependency::new("D", "Z")),
+            },
+            TestCase {
+                input: String::from("Step mix must be finished before step bake_2 can begin."),
+                output: Ok(Dependency::new("mix", "bake_2")),
+            },
+            TestCase {
+                input: String::from("Step A must be finished before step B"),
+                output: Err(ParseError(String::from(
+                    "Step A must be finished before step B",
+                ))),
             },
         ];
 
         for ref case in &cases[..] {
-            assert_eq!(case.output, Dependency::from_str(&case.input));
+            assert_eq!(case.output, case.input.parse::<Dependency>());
         }
     }
 
     #[test]
     fn test_topo_sort() {
         struct TestCase {
-            nodes: HashSet<char>,
+            nodes: HashSet<String>,
             edges: Vec<Dependency>,
-            output: Vec<char>,
+            output: Vec<&'static str>,
         }
 
         let cases: Vec<TestCase> = vec![
             TestCase {
-                nodes: HashSet::from_iter(['A', 'B', 'C'].iter().cloned()),
-                edges: vec![Dependency::new('A', 'C'), Dependency::new('C', 'B')],
-                output: vec!['A', 'C', 'B'],
+                nodes: names(&["A", "B", "C"]),
+                edges: vec![Dependency::new("A", "C"), Dependency::new("C", "B")],
+                output: vec!["A", "C", "B"],
             },
             TestCase {
-                nodes: HashSet::from_iter(['A', 'B', 'C'].iter().cloned()),
-                edges: vec![Dependency::new('A', 'C'), Dependency::new('A', 'B')],
-                output: vec!['A', 'B', 'C'],
+                nodes: names(&["A", "B", "C"]),
+                edges: vec![Dependency::new("A", "C"), Dependency::new("A", "B")],
+                output: vec!["A", "B", "C"],
             },
             TestCase {
-                nodes: HashSet::from_iter(['A', 'B', 'C', 'D', 'E', 'F'].iter().cloned()),
+                nodes: names(&["A", "B", "C", "D", "E", "F"]),
+                edges: example(),
+                output: vec!["C", "A", "B", "D", "F", "E"],
+            },
+        ];
+
+        for case in cases {
+            assert_eq!(
+                case.output,
+                topological_sort(&case.nodes, &case.edges).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_topo_sort_cycle() {
+        struct TestCase {
+            edges: Vec<Dependency>,
+            output: Vec<&'static str>,
+        }
+
+        let cases: Vec<TestCase> = vec![
+            TestCase {
+                edges: vec![Dependency::new("A", "A")],
+                output: vec!["A"],
+            },
+            TestCase {
+                // nothing can even start
+                edges: vec![Dependency::new("A", "B"), Dependency::new("B", "A")],
+                output: vec!["A", "B"],
+            },
+            TestCase {
+                // the cycle is behind a step that can go, and has a
+                // step hanging off it that's stuck too
                 edges: vec![
-                    Dependency::new('C', 'A'),
-                    Dependency::new('C', 'F'),
-                    Dependency::new('A', 'B'),
-                    Dependency::new('A', 'D'),
-                    Dependency::new('B', 'E'),
-                    Dependency::new('D', 'E'),
-                    Dependency::new('F', 'E'),
+                    Dependency::new("A", "D"),
+                    Dependency::new("D", "C"),
+                    Dependency::new("C", "B"),
+                    Dependency::new("B", "D"),
+                    Dependency::new("C", "E"),
                 ],
-                output: vec!['C', 'A', 'B', 'D', 'F', 'E'],
+                output: vec!["B", "D", "C"],
             },
         ];
 
         for case in cases {
-            assert_eq!(case.output, topological_sort(&case.nodes, &case.edges));
+            let cycle = Cycle(case.output.iter().map(|&name| String::from(name)).collect());
+            assert_eq!(Err(cycle), topological_sort(&HashSet::new(), &case.edges));
         }
+        assert_eq!(
+            "B -> D -> C -> B",
+            Cycle(vec![
+                String::from("B"),
+                String::from("D"),
+                String::from("C")
+            ])
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn test_strongly_connected_components() {
+        let mut edges = example();
+        assert_eq!(
+            vec![
+                vec!["C"],
+                vec!["A"],
+                vec!["B"],
+                vec!["D"],
+                vec!["F"],
+                vec!["E"]
+            ],
+            strongly_connected_components(&HashSet::new(), &edges)
+        );
+        edges.push(Dependency::new("E", "A"));
+        edges.push(Dependency::new("G", "G"));
+        assert_eq!(
+            vec![
+                vec!["C"],
+                vec!["F"],
+                vec!["A", "B", "D", "E"],
+                vec!["G"],
+                vec!["H"]
+            ],
+            strongly_connected_components(&names(&["H"]), &edges)
+        );
     }
 
     #[test]
-    fn test_time_for_step() {
+    fn test_long_chain() {
+        // deep enough to overflow the stack if anything recursed
+        let names: Vec<String> = (0..100_000).map(|i| format!("s{:06}", i)).collect();
+        let edges: Vec<Dependency> = names
+            .windows(2)
+            .map(|pair| Dependency::new(&pair[0], &pair[1]))
+            .collect();
+        assert_eq!(names, topological_sort(&HashSet::new(), &edges).unwrap());
+        assert_eq!(
+            100_000,
+            strongly_connected_components(&HashSet::new(), &edges).len()
+        );
+    }
+
+    #[test]
+    fn test_lettered() {
+        assert_eq!(Some(Step::new("A", 1)), Step::lettered("A", 0));
+        assert_eq!(Some(Step::new("A", 11)), Step::lettered("A", 10));
+        assert_eq!(Some(Step::new("Z", 86)), Step::lettered("Z", 60));
+        assert_eq!(None, Step::lettered("a", 0));
+        assert_eq!(None, Step::lettered("AB", 0));
+    }
+
+    #[test]
+    fn test_timed_steps() {
+        let durations = read_durations("mix 5\nbake 30\n\nrest 10\n").unwrap();
+        assert_eq!(Some(&30), durations.get("bake"));
+        assert_eq!(
+            Err(ParseError(String::from("bake"))),
+            read_durations("bake")
+        );
+        assert_eq!(
+            Err(ParseError(String::from("bake -5"))),
+            read_durations("mix 5\nbake -5\n")
+        );
+
+        let dependencies = vec![Dependency::new("mix", "bake")];
+        assert_eq!(
+            Ok(vec![
+                Step::new("bake", 30),
+                Step::new("mix", 5),
+                Step::new("rest", 10)
+            ]),
+            timed_steps(&dependencies, Some(&durations), 60)
+        );
+        assert_eq!(
+            Err(ScheduleError::NoDuration(String::from("bake"))),
+            timed_steps(&dependencies, None, 60)
+        );
+    }
+
+    #[test]
+    fn test_schedule() {
+        let dependencies = example();
+        let steps = timed_steps(&dependencies, None, 0).unwrap();
+        let task = |worker, step, start, end| Task {
+            worker,
+            step: String::from(step),
+            start,
+            end,
+        };
+        assert_eq!(
+            Ok(vec![
+                task(0, "C", 0, 3),
+                task(0, "A", 3, 4),
+                task(1, "F", 3, 9),
+                task(0, "B", 4, 6),
+                task(0, "D", 6, 10),
+                task(0, "E", 10, 15),
+            ]),
+            schedule(2, &steps, &dependencies, Policy::Alphabetical)
+        );
+        assert_eq!(
+            Err(ScheduleError::NoWorkers),
+            schedule(0, &steps, &dependencies, Policy::Alphabetical)
+        );
+
+        let cycle = vec![Dependency::new("A", "B"), Dependency::new("B", "A")];
+        let steps = vec![Step::new("A", 1), Step::new("B", 1), Step::new("C", 1)];
+        assert_eq!(
+            Err(ScheduleError::Cycle(Cycle(vec![
+                String::from("A"),
+                String::from("B")
+            ]))),
+            schedule(1, &steps, &cycle, Policy::Alphabetical)
+        );
+    }
+
+    #[test]
+    fn test_policies() {
         struct TestCase {
-            step: Step,
-            output: i32,
+            steps: Vec<Step>,
+            dependencies: Vec<Dependency>,
+            policy: Policy,
+            output: i64,
         }
-
-        let mut cases: Vec<TestCase> = vec![
+        // two workers; a long step left until last, or a short step that
+        // holds up a long one
+        let long_last = || vec![Step::new("A", 1), Step::new("B", 1), Step::new("C", 5)];
+        let held_up = || {
+            vec![
+                Step::new("A", 1),
+                Step::new("B", 1),
+                Step::new("Y", 1),
+                Step::new("Z", 5),
+            ]
+        };
+        let cases: Vec<TestCase> = vec![
+            TestCase {
+                steps: long_last(),
+                dependencies: vec![],
+                policy: Policy::Alphabetical,
+                output: 6,
+            },
+            TestCase {
+                steps: long_last(),
+                dependencies: vec![],
+                policy: Policy::LongestFirst,
+                output: 5,
+            },
+            TestCase {
+                steps: long_last(),
+                dependencies: vec![],
+                policy: Policy::CriticalPath,
+                output: 5,
+            },
+            TestCase {
+                steps: held_up(),
+                dependencies: vec![Dependency::new("Y", "Z")],
+                policy: Policy::Alphabetical,
+                output: 7,
+            },
             TestCase {
-                step: Step::new('A', 0),
-                output: 1,
+                steps: held_up(),
+                dependencies: vec![Dependency::new("Y", "Z")],
+                policy: Policy::LongestFirst,
+                output: 7,
             },
             TestCase {
-                step: Step::new('A', 10),
-                output: 11,
+                steps: held_up(),
+                dependencies: vec![Dependency::new("Y", "Z")],
+                policy: Policy::MostDependents,
+                output: 6,
             },
             TestCase {
-                step: Step::new('B', 2),
-                output: 4,
+                steps: held_up(),
+                dependencies: vec![Dependency::new("Y", "Z")],
+                policy: Policy::CriticalPath,
+                output: 6,
             },
         ];
 
-        for case in cases.iter_mut() {
-            assert_eq!(case.output, case.step.time_left);
-            assert!(!case.step.is_done());
-            for _i in 0..case.step.time_left {
-                case.step.tick();
-            }
-            assert!(case.step.is_done());
+        for case in &cases[..] {
+            let tasks = schedule(2, &case.steps, &case.dependencies, case.policy).unwrap();
+            assert_eq!(case.output, time_needed(&tasks), "{:?}", case.policy);
         }
+
+        for &policy in &POLICIES {
+            assert_eq!(Some(policy), Policy::from_name(policy.name()));
+        }
+        assert_eq!(None, Policy::from_name("input.txt"));
+    }
+
+    #[test]
+    fn test_render_table() {
+        let dependencies = example();
+        let steps = timed_steps(&dependencies, None, 0).unwrap();
+        let tasks = schedule(2, &steps, &dependencies, Policy::Alphabetical).unwrap();
+        let table = render_table(2, &tasks);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(17, lines.len());
+        assert_eq!("Second   Worker 1    Worker 2    Done", lines[0]);
+        assert_eq!("     0   C           .", lines[1]);
+        assert_eq!("     4   B           F           CA", lines[5]);
+        assert_eq!("    15   .           .           CABFDE", lines[16]);
+
+        let steps = vec![Step::new("mix", 2), Step::new("bake", 1)];
+        let dependencies = vec![Dependency::new("mix", "bake")];
+        let tasks = schedule(1, &steps, &dependencies, Policy::Alphabetical).unwrap();
+        assert_eq!(
+            "     3   .           mix,bake",
+            render_table(1, &tasks).lines().last().unwrap()
+        );
     }
 
     #[test]
-    fn test_steps_available() {
-        let mut steps: HashMap<char, Step> = HashMap::new();
-        steps.insert('A', Step::new('A', 0));
-        steps.insert('B', Step::new('B', 0));
-        steps.insert('C', Step::new('C', 0));
-        let mut dependencies: HashMap<char, Vec<char>> = HashMap::new();
-        dependencies.insert('C', vec!['B', 'A']);
-        dependencies.insert('B', vec!['A']);
-
-        assert_eq!(vec!['A'], steps_available(&steps, &dependencies));
-        steps.get_mut(&'A').unwrap().tick();
-        assert_eq!(vec!['B'], steps_available(&steps, &dependencies));
-        steps.get_mut(&'B').unwrap().time_left = 0;
-        assert_eq!(vec!['C'], steps_available(&steps, &dependencies));
+    fn test_critical_path() {
+        let dependencies = example();
+        let steps = timed_steps(&dependencies, None, 0).unwrap();
+        let critical = critical_path(&steps, &dependencies).unwrap();
+        assert_eq!(14, critical.length);
+        assert_eq!(vec!["C", "F", "E"], critical.path);
+        let slack: Vec<(&str, i64)> = critical
+            .timings
+            .iter()
+            .map(|timing| (timing.name.as_str(), timing.slack()))
+            .collect();
+        assert_eq!(
+            vec![("A", 1), ("B", 3), ("C", 0), ("D", 1), ("E", 0), ("F", 0)],
+            slack
+        );
+
+        let cycle = vec![Dependency::new("A", "B"), Dependency::new("B", "A")];
+        assert_eq!(
+            Err(ScheduleError::Cycle(Cycle(vec![
+                String::from("A"),
+                String::from("B")
+            ]))),
+            critical_path(&timed_steps(&cycle, None, 0).unwrap(), &cycle)
+        );
+    }
+
+    #[test]
+    fn test_render_gantt() {
+        let dependencies = vec![Dependency::new("<mix>", "bake")];
+        let steps = vec![Step::new("<mix>", 200), Step::new("bake", 100)];
+        let tasks = schedule(2, &steps, &dependencies, Policy::Alphabetical).unwrap();
+        let critical = critical_path(&steps, &dependencies).unwrap();
+        let svg = render_gantt(2, &tasks, &critical.path);
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(2, svg.matches("<rect ").count());
+        assert_eq!(2, svg.matches("#e0603c").count());
+        assert!(svg.contains(">&lt;mix&gt;</text>"));
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let dependencies = vec![
+            Dependency::new("B", "A"),
+            Dependency::new("B", "C"),
+            Dependency::new("C", "A"),
+        ];
+        assert_eq!(
+            "digraph steps {\n\
+             \x20   rankdir=LR;\n\
+             \x20   node [shape=box];\n\
+             \x20   \"A\" [label=\"A\"];\n\
+             \x20   \"B\" [label=\"B\"];\n\
+             \x20   \"C\" [label=\"C\"];\n\
+             \x20   \"B\" -> \"A\";\n\
+             \x20   \"B\" -> \"C\";\n\
+             \x20   \"C\" -> \"A\";\n\
+             }\n",
+            to_dot(&dependencies, &[], &DotOptions::default()).unwrap()
+        );
+
+        let steps = timed_steps(&dependencies, None, 0).unwrap();
+        let options = DotOptions {
+            durations: true,
+            order: true,
+            critical: true,
+        };
+        let dot = to_dot(&dependencies, &steps, &options).unwrap();
+        assert!(dot.contains("    \"B\" [label=\"1. B\\n2s\", color=red, penwidth=2];\n"));
+        assert!(dot.contains("    \"A\" [label=\"3. A\\n1s\", color=red, penwidth=2];\n"));
+        assert!(dot.contains("    \"B\" -> \"C\" [color=red, penwidth=2];\n"));
+        assert!(dot.contains("    \"B\" -> \"A\";\n"));
+
+        let quoted = vec![Dependency::new("say \"hi\"", "wave")];
+        let dot = to_dot(&quoted, &[], &DotOptions::default()).unwrap();
+        assert!(dot.contains("    \"say \\\"hi\\\"\" -> \"wave\";\n"));
     }
 
     #[test]
@@ -330,59 +1397,67 @@ mod tests {
             output: i64,
         }
 
+        let lettered = |names: &[&str]| -> Vec<Step> {
+            names
+                .iter()
+                .map(|name| Step::lettered(name, 0).unwrap())
+                .collect()
+        };
         let cases: Vec<TestCase> = vec![
             TestCase {
                 n_workers: 1,
-                steps: vec![Step::new('A', 0), Step::new('B', 0)],
+                steps: lettered(&["A", "B"]),
                 dependencies: vec![],
                 output: 3,
             },
             TestCase {
                 n_workers: 2,
-                steps: vec![Step::new('A', 0), Step::new('B', 0)],
+                steps: lettered(&["A", "B"]),
                 dependencies: vec![],
                 output: 2,
             },
             TestCase {
                 n_workers: 3,
-                steps: vec![Step::new('A', 0), Step::new('B', 0)],
+                steps: lettered(&["A", "B"]),
                 dependencies: vec![],
                 output: 2,
             },
             TestCase {
                 n_workers: 2,
-                steps: vec![Step::new('A', 0), Step::new('B', 0)],
-                dependencies: vec![Dependency::new('A', 'B')],
+                steps: lettered(&["A", "B"]),
+                dependencies: vec![Dependency::new("A", "B")],
                 output: 3,
             },
+            TestCase {
+                n_workers: 2,
+                steps: lettered(&["A", "B", "C", "D", "E", "F"]),
+                dependencies: example(),
+                output: 15,
+            },
             TestCase {
                 n_workers: 2,
                 steps: vec![
-                    Step::new('A', 0),
-                    Step::new('B', 0),
-                    Step::new('C', 0),
-                    Step::new('D', 0),
-                    Step::new('E', 0),
-                    Step::new('F', 0),
+                    Step::new("mix", 5),
+                    Step::new("bake", 30),
+                    Step::new("rest", 0),
                 ],
                 dependencies: vec![
-                    Dependency::new('C', 'A'),
-                    Dependency::new('C', 'F'),
-                    Dependency::new('A', 'B'),
-                    Dependency::new('A', 'D'),
-                    Dependency::new('B', 'E'),
-                    Dependency::new('D', 'E'),
-                    Dependency::new('F', 'E'),
+                    Dependency::new("mix", "bake"),
+                    Dependency::new("bake", "rest"),
                 ],
-                output: 15,
+                output: 35,
             },
         ];
 
         for ref case in &cases[..] {
-            assert_eq!(
-                case.output,
-                time_needed(case.n_workers, &case.steps, &case.dependencies)
-            );
+            let tasks = schedule(
+                case.n_workers,
+                &case.steps,
+                &case.dependencies,
+                Policy::Alphabetical,
+            )
+            .unwrap();
+            assert_eq!(case.output, time_needed(&tasks));
         }
     }
 }
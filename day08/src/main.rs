@@ -1,100 +1,343 @@
 #![allow(unused_doc_comments)]
 
 use std::env;
+use std::fmt;
 use std::fs;
+use std::mem;
+use std::str::FromStr;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let task = &args[1];
     let filename = &args[2];
 
-    let tree: Vec<i32> = fs::read_to_string(filename)
-        .unwrap()
-        .trim()
-        .split_whitespace()
-        .map(|x| x.parse::<i32>().unwrap())
-        .collect();
+    let tree: Node = fs::read_to_string(filename).unwrap().parse().unwrap();
 
     if task == "meta" {
         println!("{}", sum_metadata(&tree));
     } else if task == "value" {
         println!("{}", node_value(&tree));
+    } else if task == "tree" {
+        print!("{}", tree);
+    } else if task == "flat" {
+        let flat: Vec<String> = tree.to_numbers().iter().map(|n| n.to_string()).collect();
+        println!("{}", flat.join(" "));
     } else {
         panic!("Don't know how to '{}'", task);
     }
 }
 
-fn sum_metadata(tree: &[i32]) -> i32 {
-    /// sum all the "metadata" items from the tree
-    let (s, _) = _sum_metadata(tree);
-    s
+#[derive(Debug, PartialEq, Eq)]
+enum LicenseError {
+    NotANumber(String),     // a word that isn't a count or an entry
+    NegativeCount(usize),   // where in the numbers the header has one
+    Truncated,              // the numbers ran out partway through a node
+    TrailingNumbers(usize), // how many are left over after the root node
 }
 
-fn _sum_metadata(tree: &[i32]) -> (i32, usize) {
-    /// recursive helper that returns both the sum and how many items are consumed
-    let n_children = tree[0];
-    let n_metadata = tree[1] as usize;
+// Everything walks the tree with its own stack rather than recursing, so
+// a license nested millions deep doesn't overflow
+#[derive(Debug, Eq)]
+struct Node {
+    children: Vec<Node>,
+    metadata: Vec<i32>,
+}
 
-    if n_children == 0 {
-        (
-            tree[2..(2 + n_metadata)].iter().sum(),
-            2 + (n_metadata as usize),
-        )
-    } else {
-        let mut sum = 0;
-        let mut idx = 2;
-        // recursively sum the child nodes, tracking how many items we use up
-        for _i in 0..n_children {
-            let (child_sum, child_idx) = _sum_metadata(&tree[idx..]);
-            sum += child_sum;
-            idx += child_idx;
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Visit {
+    Enter, // on the way down, before the children
+    Leave, // on the way back up, after them
+}
+
+struct Partial {
+    node: Node,
+    children_left: usize,
+    n_metadata: usize,
+}
+
+impl Node {
+    fn from_numbers(numbers: &[i32]) -> Result<Node, LicenseError> {
+        /// Build the tree from the license file's numbers: a header with
+        /// the number of children and of metadata entries, then the
+        /// children, then the metadata
+        let mut idx = 0;
+        let header = |idx: &mut usize| -> Result<Partial, LicenseError> {
+            if *idx + 2 > numbers.len() {
+                return Err(LicenseError::Truncated);
+            }
+            let (n_children, n_metadata) = (numbers[*idx], numbers[*idx + 1]);
+            if n_children < 0 {
+                return Err(LicenseError::NegativeCount(*idx));
+            }
+            if n_metadata < 0 {
+                return Err(LicenseError::NegativeCount(*idx + 1));
+            }
+            *idx += 2;
+            Ok(Partial {
+                node: Node {
+                    children: Vec::new(),
+                    metadata: Vec::new(),
+                },
+                children_left: n_children as usize,
+                n_metadata: n_metadata as usize,
+            })
+        };
+
+        // the nodes still waiting on children, from the root down
+        let mut stack: Vec<Partial> = vec![header(&mut idx)?];
+        let root = loop {
+            let top = stack.last_mut().unwrap();
+            if top.children_left > 0 {
+                top.children_left -= 1;
+                let child = header(&mut idx)?;
+                stack.push(child);
+                continue;
+            }
+            let mut done = stack.pop().unwrap();
+            if numbers.len() - idx < done.n_metadata {
+                return Err(LicenseError::Truncated);
+            }
+            done.node.metadata = numbers[idx..(idx + done.n_metadata)].to_vec();
+            idx += done.n_metadata;
+            match stack.last_mut() {
+                Some(parent) => parent.node.children.push(done.node),
+                None => break done.node,
+            }
+        };
+        if idx < numbers.len() {
+            return Err(LicenseError::TrailingNumbers(numbers.len() - idx));
         }
-        // use up the remaining metadata items
-        for _i in 0..n_metadata {
-            sum += tree[idx + _i];
+        Ok(root)
+    }
+
+    fn walk<'a, V: FnMut(Visit, &'a Node, &[bool])>(&'a self, mut visit: V) {
+        /// Visit every node, entering it before its children and leaving
+        /// it after them. Each visit also gets whether each node on the way
+        /// down from the root, this one included, is its parent's last child
+        let mut stack: Vec<(&Node, usize)> = vec![(self, 0)]; // node, next child
+        let mut last: Vec<bool> = vec![true];
+        visit(Visit::Enter, self, &last);
+        while let Some(&(node, next)) = stack.last() {
+            if next < node.children.len() {
+                stack.last_mut().unwrap().1 += 1;
+                let child = &node.children[next];
+                last.push(next + 1 == node.children.len());
+                visit(Visit::Enter, child, &last);
+                stack.push((child, 0));
+            } else {
+                visit(Visit::Leave, node, &last);
+                stack.pop();
+                last.pop();
+            }
         }
-        idx += n_metadata;
-        (sum, idx)
+    }
+
+    fn to_numbers(&self) -> Vec<i32> {
+        /// Flatten the tree back into the license file's format
+        let mut numbers: Vec<i32> = Vec::new();
+        self.walk(|visit, node, _| match visit {
+            Visit::Enter => {
+                numbers.push(node.children.len() as i32);
+                numbers.push(node.metadata.len() as i32);
+            }
+            Visit::Leave => numbers.extend(&node.metadata),
+        });
+        numbers
+    }
+
+    fn fold<T, F: Fn(&Node, Vec<T>) -> T>(&self, f: &F) -> T {
+        /// Combine each node with what its children folded to, from the
+        /// leaves up
+        let mut results: Vec<T> = Vec::new();
+        self.walk(|visit, node, _| {
+            if visit == Visit::Leave {
+                let children = results.split_off(results.len() - node.children.len());
+                results.push(f(node, children));
+            }
+        });
+        results.pop().unwrap()
     }
 }
 
-fn node_value(tree: &[i32]) -> i32 {
-    /// return the value of the root node of the tree
-    let (v, _) = _node_value(tree);
-    v
+impl FromStr for Node {
+    type Err = LicenseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let numbers = s
+            .split_whitespace()
+            .map(|word| {
+                word.parse::<i32>()
+                    .map_err(|_| LicenseError::NotANumber(String::from(word)))
+            })
+            .collect::<Result<Vec<i32>, LicenseError>>()?;
+        Node::from_numbers(&numbers)
+    }
 }
 
-fn _node_value(tree: &[i32]) -> (i32, usize) {
-    /// recursive helper that returns both the value and how many items are consumed
-    let n_children = tree[0];
-    let n_metadata = tree[1] as usize;
+impl PartialEq for Node {
+    fn eq(&self, other: &Node) -> bool {
+        // the flat format has exactly one way to write each tree
+        self.to_numbers() == other.to_numbers()
+    }
+}
 
-    if n_children == 0 {
-        (
-            tree[2..(2 + n_metadata)].iter().sum(),
-            2 + (n_metadata as usize),
-        )
-    } else {
-        let mut children: Vec<i32> = Vec::new();
-        let mut idx = 2;
-        for _i in 0..n_children {
-            let (child_value, child_idx) = _node_value(&tree[idx..]);
-            children.push(child_value);
-            idx += child_idx;
+impl Drop for Node {
+    fn drop(&mut self) {
+        // take the descendants apart one at a time, so dropping a node
+        // never has to drop a deep tree beneath it
+        let mut descendants = mem::take(&mut self.children);
+        while let Some(mut node) = descendants.pop() {
+            descendants.append(&mut node.children);
         }
-        let mut value: i32 = 0;
-        for child in tree[idx..(idx + n_metadata)].iter() {
-            value += children.get((*child - 1) as usize).unwrap_or(&0);
-        }
-        idx += n_metadata;
-        (value, idx)
     }
 }
 
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // each node hangs off a branch, under the lines down from its
+        // ancestors that have more children to come
+        let mut result = Ok(());
+        self.walk(|visit, node, last| {
+            if visit == Visit::Leave || result.is_err() {
+                return;
+            }
+            let mut line = String::new();
+            if last.len() > 1 {
+                for &ancestor_last in &last[1..(last.len() - 1)] {
+                    line.push_str(if ancestor_last { "    " } else { "│   " });
+                }
+                line.push_str(if last[last.len() - 1] {
+                    "└── "
+                } else {
+                    "├── "
+                });
+            }
+            result = writeln!(f, "{}{:?}", line, node.metadata);
+        });
+        result
+    }
+}
+
+fn sum_metadata(tree: &Node) -> i32 {
+    /// sum all the "metadata" items from the tree
+    tree.fold(&|node: &Node, children: Vec<i32>| {
+        children.iter().sum::<i32>() + node.metadata.iter().sum::<i32>()
+    })
+}
+
+fn node_value(tree: &Node) -> i32 {
+    /// return the value of the root node of the tree: the sum of its
+    /// metadata if it has no children, or else the sum of the values of
+    /// the children its metadata entries point to
+    tree.fold(&|node: &Node, children: Vec<i32>| {
+        if children.is_empty() {
+            node.metadata.iter().sum()
+        } else {
+            node.metadata
+                .iter()
+                .map(|&entry| children.get((entry - 1) as usize).unwrap_or(&0))
+                .sum()
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const EXAMPLE: [i32; 16] = [2, 3, 0, 3, 10, 11, 12, 1, 1, 0, 1, 99, 2, 1, 1, 2];
+
+    #[test]
+    fn test_parse() {
+        let leaf = |metadata: Vec<i32>| Node {
+            children: vec![],
+            metadata,
+        };
+        let tree = Node {
+            children: vec![
+                leaf(vec![10, 11, 12]),
+                Node {
+                    children: vec![leaf(vec![99])],
+                    metadata: vec![2],
+                },
+            ],
+            metadata: vec![1, 1, 2],
+        };
+        assert_eq!(EXAMPLE.to_vec(), tree.to_numbers());
+        assert_eq!(Ok(tree), Node::from_numbers(&EXAMPLE));
+    }
+
+    #[test]
+    fn test_errors() {
+        struct TestCase {
+            input: &'static str,
+            output: LicenseError,
+        }
+
+        let cases: Vec<TestCase> = vec![
+            TestCase {
+                input: "",
+                output: LicenseError::Truncated,
+            },
+            TestCase {
+                input: "1 1 0",
+                output: LicenseError::Truncated,
+            },
+            TestCase {
+                input: "0 3 1 2",
+                output: LicenseError::Truncated,
+            },
+            TestCase {
+                input: "1 1 0 1 7",
+                output: LicenseError::Truncated,
+            },
+            TestCase {
+                input: "1 1 0 -1 7 1",
+                output: LicenseError::NegativeCount(3),
+            },
+            TestCase {
+                input: "0 1 7 0 1 7",
+                output: LicenseError::TrailingNumbers(3),
+            },
+            TestCase {
+                input: "0 1 seven",
+                output: LicenseError::NotANumber(String::from("seven")),
+            },
+        ];
+
+        for case in &cases[..] {
+            assert_eq!(Err(&case.output), case.input.parse::<Node>().as_ref());
+        }
+    }
+
+    #[test]
+    fn test_deep() {
+        // a chain of a million nodes, each pointing at its only child
+        let depth = 1_000_000;
+        let mut numbers: Vec<i32> = Vec::new();
+        for _ in 1..depth {
+            numbers.extend(&[1, 1]);
+        }
+        numbers.extend(&[0, 1]);
+        numbers.extend(vec![1; depth]);
+
+        let tree = Node::from_numbers(&numbers).unwrap();
+        assert_eq!(depth as i32, sum_metadata(&tree));
+        assert_eq!(1, node_value(&tree));
+        assert_eq!(numbers, tree.to_numbers());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            "[1, 1, 2]\n\
+             ├── [10, 11, 12]\n\
+             └── [2]\n\
+             \x20   └── [99]\n",
+            Node::from_numbers(&EXAMPLE).unwrap().to_string()
+        );
+    }
+
     #[test]
     fn test_sum_metadata() {
         struct TestCase {
@@ -108,13 +351,16 @@ mod tests {
                 output: 10,
             },
             TestCase {
-                input: vec![2, 3, 0, 3, 10, 11, 12, 1, 1, 0, 1, 99, 2, 1, 1, 2],
+                input: EXAMPLE.to_vec(),
                 output: 138,
             },
         ];
 
         for ref case in &cases[..] {
-            assert_eq!(case.output, sum_metadata(&case.input));
+            assert_eq!(
+                case.output,
+                sum_metadata(&Node::from_numbers(&case.input).unwrap())
+            );
         }
     }
 
@@ -131,13 +377,16 @@ mod tests {
                 output: 14, // i think
             },
             TestCase {
-                input: vec![2, 3, 0, 3, 10, 11, 12, 1, 1, 0, 1, 99, 2, 1, 1, 2],
+                input: EXAMPLE.to_vec(),
                 output: 66,
             },
         ];
 
         for ref case in &cases[..] {
-            assert_eq!(case.output, node_value(&case.input));
+            assert_eq!(
+                case.output,
+                node_value(&Node::from_numbers(&case.input).unwrap())
+            );
         }
     }
 }
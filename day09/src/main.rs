@@ -1,86 +1,208 @@
 #![allow(unused_doc_comments)]
 
-extern crate doubly;
-
+use std::collections::VecDeque;
 use std::env;
 use std::fmt;
-
-use doubly::DoublyLinkedList;
+use std::num::NonZeroU64;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let task = &args[1];
 
-    if task == "highscore" {
-        let n_players = args[2].parse::<usize>().unwrap();
-        let last_marble = args[3].parse::<u64>().unwrap();
-        println!("{}", high_score(n_players, last_marble));
+    if task == "highscore" || task == "scores" {
+        // highscore <players> <last marble> [divisor] [removal] [placement] [board]
+        let n_players = args
+            .get(2)
+            .expect("Need a number of players")
+            .parse::<usize>()
+            .expect("Couldn't parse the number of players");
+        if n_players == 0 {
+            panic!("Need at least one player");
+        }
+        let last_marble = args
+            .get(3)
+            .expect("Need a last marble")
+            .parse::<u64>()
+            .expect("Couldn't parse the last marble");
+        let rules = rules_from_args(&args[4..]);
+        let result = high_score(n_players, last_marble, &rules);
+        if task == "highscore" {
+            println!("{}", result.high_score());
+        } else {
+            for (player, score) in result.scores.iter().enumerate() {
+                println!("[{}] {}", player + 1, score);
+            }
+            match result.lead_taken {
+                Some(turn) => println!(
+                    "Player {} wins, in the lead from turn {}",
+                    result.winner + 1,
+                    turn
+                ),
+                None => println!("Tied for the win"),
+            }
+        }
     } else {
         panic!("Don't know how to '{}'", task);
     }
 }
 
+fn rules_from_args(args: &[String]) -> Rules {
+    /// The puzzle's rules, except for any given: the scoring divisor, the
+    /// removal and placement offsets, and the starting board as
+    /// comma-separated marbles clockwise from the current one
+    let mut rules = Rules::default();
+    if let Some(divisor) = args.first() {
+        rules.scoring_divisor = divisor
+            .parse()
+            .expect("Couldn't parse the scoring divisor, which can't be 0");
+    }
+    if let Some(removal) = args.get(1) {
+        rules.removal_offset = removal.parse().expect("Couldn't parse the removal offset");
+    }
+    if let Some(placement) = args.get(2) {
+        rules.placement_offset = placement
+            .parse()
+            .expect("Couldn't parse the placement offset");
+    }
+    if let Some(board) = args.get(3) {
+        rules.starting_board = board
+            .split(',')
+            .map(|m| m.parse().expect("Couldn't parse the starting board"))
+            .collect();
+    }
+    rules
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Rules {
+    scoring_divisor: NonZeroU64, // marbles that are multiples of this score
+    removal_offset: usize,       // counter-clockwise to the marble taken then
+    placement_offset: usize,     // how far clockwise a placed marble ends up
+    starting_board: Vec<u64>,    // clockwise from the current marble
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules {
+            scoring_divisor: NonZeroU64::new(23).unwrap(),
+            removal_offset: 7,
+            placement_offset: 2,
+            starting_board: vec![0],
+        }
+    }
+}
+
 struct GameBoard {
-    board: DoublyLinkedList<u64>,
-    current: usize,
+    // the marbles clockwise, rotated so the current marble is at the back
+    // and the one clockwise of it is at the front
+    board: VecDeque<u64>,
 }
 
 impl GameBoard {
-    fn new() -> GameBoard {
-        let mut board = DoublyLinkedList::new();
-        board.push_back(0);
-        GameBoard { board, current: 0 }
+    fn new(starting_board: &[u64]) -> GameBoard {
+        let mut board: VecDeque<u64> = starting_board.iter().cloned().collect();
+        if !board.is_empty() {
+            board.rotate_left(1);
+        }
+        GameBoard { board }
     }
 
-    fn insert(&mut self, value: u64) {
+    fn insert(&mut self, value: u64, offset: usize) {
+        /// Place the marble so it ends up offset clockwise of the current
+        /// marble (between the ones 1 and 2 clockwise for the puzzle's 2),
+        /// and make it current
         let size = self.board.len();
-        let insert_at = (self.current + 2) % size;
-        if insert_at == 0 {
-            self.board.push_back(value);
-            self.current = size;
-        } else {
-            self.board.insert(insert_at, value);
-            self.current = insert_at;
+        if size > 0 {
+            self.board.rotate_left((offset % size + size - 1) % size);
         }
+        self.board.push_back(value);
     }
 
-    fn remove(&mut self) -> u64 {
+    fn remove(&mut self, offset: usize) -> Option<u64> {
+        /// Take out the marble offset counter-clockwise of the current
+        /// marble, making the one clockwise of it current
         let size = self.board.len();
-        let remove_at = (self.current + size - 7) % size;
-        self.current = remove_at;
-        self.board.remove(remove_at)
+        if size == 0 {
+            return None;
+        }
+        self.board.rotate_right(offset % size);
+        let value = self.board.pop_back();
+        if !self.board.is_empty() {
+            self.board.rotate_left(1);
+        }
+        value
     }
 }
 
 impl fmt::Display for GameBoard {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for (i, value) in self.board.iter().enumerate() {
-            if i == self.current {
-                write!(f, "({}) ", value);
+        // starting from marble 0 like the puzzle does
+        let size = self.board.len();
+        let start = self.board.iter().position(|&v| v == 0).unwrap_or(0);
+        for i in 0..size {
+            let idx = (start + i) % size;
+            if idx == size - 1 {
+                write!(f, "({}) ", self.board[idx])?;
             } else {
-                write!(f, "{} ", value);
+                write!(f, "{} ", self.board[idx])?;
             }
         }
         Ok(())
     }
 }
 
-fn high_score(n_players: usize, last_marble: u64) -> u64 {
-    let mut board = GameBoard::new();
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct GameResult {
+    scores: Vec<u64>,        // by player, starting from the one who places marble 1
+    winner: usize,           // the player with the high score, the first one on a tie
+    lead_taken: Option<u64>, // the turn the winner went ahead for good, unless it's a tie
+}
+
+impl GameResult {
+    fn high_score(&self) -> u64 {
+        self.scores[self.winner]
+    }
+}
+
+fn high_score(n_players: usize, last_marble: u64, rules: &Rules) -> GameResult {
+    /// Play the game through, turn n placing marble n
+    let mut board = GameBoard::new(&rules.starting_board);
     let mut scores: Vec<u64> = vec![0; n_players];
 
+    // who's strictly ahead, and since when
+    let mut leader: Option<usize> = None;
+    let mut best: u64 = 0;
+    let mut lead_taken: Option<u64> = None;
+
     let mut player: usize = 0;
     for marble in 1..=last_marble {
-        if marble % 23 != 0 {
-            board.insert(marble);
+        if marble % rules.scoring_divisor != 0 {
+            board.insert(marble, rules.placement_offset);
         } else {
-            let thing = board.remove();
+            let thing = board.remove(rules.removal_offset).unwrap_or(0);
             scores[player] += marble;
             scores[player] += thing;
+            if scores[player] > best {
+                if leader != Some(player) {
+                    lead_taken = Some(marble);
+                }
+                leader = Some(player);
+                best = scores[player];
+            } else if scores[player] == best {
+                leader = None;
+            }
         }
         player = (player + 1) % n_players;
     }
-    *scores.iter().max().unwrap()
+
+    let winner = (0..n_players)
+        .find(|&player| scores[player] == best)
+        .unwrap();
+    GameResult {
+        scores,
+        winner,
+        lead_taken: leader.and(lead_taken),
+    }
 }
 
 #[cfg(test)]
@@ -129,7 +251,110 @@ mod tests {
         ];
 
         for ref case in &cases[..] {
-            assert_eq!(case.output, high_score(case.n_players, case.last_marble));
+            assert_eq!(
+                case.output,
+                high_score(case.n_players, case.last_marble, &Rules::default()).high_score()
+            );
+        }
+    }
+
+    #[test]
+    fn test_game_result() {
+        let mut scores = vec![0; 9];
+        scores[4] = 32;
+        assert_eq!(
+            GameResult {
+                scores,
+                winner: 4,
+                lead_taken: Some(23),
+            },
+            high_score(9, 25, &Rules::default())
+        );
+
+        // nobody has scored yet
+        assert_eq!(
+            GameResult {
+                scores: vec![0; 3],
+                winner: 0,
+                lead_taken: None,
+            },
+            high_score(3, 22, &Rules::default())
+        );
+    }
+
+    #[test]
+    fn test_board() {
+        let mut board = GameBoard::new(&[0]);
+        for marble in 1..=22 {
+            board.insert(marble, 2);
         }
+        assert_eq!(
+            "0 16 8 17 4 18 9 19 2 20 10 21 5 (22) 11 1 12 6 13 3 14 7 15 ",
+            board.to_string()
+        );
+        assert_eq!(Some(9), board.remove(7));
+        assert_eq!(
+            "0 16 8 17 4 18 (19) 2 20 10 21 5 22 11 1 12 6 13 3 14 7 15 ",
+            board.to_string()
+        );
+
+        let mut board = GameBoard::new(&[5, 0, 3]);
+        assert_eq!("0 3 (5) ", board.to_string());
+        board.insert(1, 2);
+        assert_eq!("0 (1) 3 5 ", board.to_string());
+        board.insert(2, 0);
+        assert_eq!("0 (2) 1 3 5 ", board.to_string());
+        board.insert(4, 1);
+        assert_eq!("0 2 (4) 1 3 5 ", board.to_string());
+
+        let mut board = GameBoard::new(&[]);
+        assert_eq!(None, board.remove(7));
+        board.insert(1, 2);
+        assert_eq!("(1) ", board.to_string());
+    }
+
+    #[test]
+    fn test_rules() {
+        // the puzzle's answer for the input, and with marbles 100 times larger
+        assert_eq!(
+            374690,
+            high_score(477, 70851, &Rules::default()).high_score()
+        );
+        assert_eq!(
+            3009951158,
+            high_score(477, 70851 * 100, &Rules::default()).high_score()
+        );
+
+        // marbles go just clockwise of the current one, so they lie in
+        // order until 5 scores along with 3 just before the current 4
+        let rules = Rules {
+            scoring_divisor: NonZeroU64::new(5).unwrap(),
+            removal_offset: 1,
+            placement_offset: 1,
+            starting_board: vec![0],
+        };
+        assert_eq!(
+            GameResult {
+                scores: vec![8],
+                winner: 0,
+                lead_taken: Some(5),
+            },
+            high_score(1, 5, &rules)
+        );
+
+        let args: Vec<String> = vec![String::from("5"), String::from("1")];
+        assert_eq!(
+            Rules {
+                scoring_divisor: NonZeroU64::new(5).unwrap(),
+                removal_offset: 1,
+                ..Rules::default()
+            },
+            rules_from_args(&args)
+        );
+        let args: Vec<String> = ["23", "7", "1", "0,4,2"]
+            .iter()
+            .map(|arg| String::from(*arg))
+            .collect();
+        assert_eq!(vec![0, 4, 2], rules_from_args(&args).starting_board);
     }
 }
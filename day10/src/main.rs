@@ -1,8 +1,8 @@
+#![allow(unused_doc_comments)]
 #[macro_use]
 extern crate lazy_static;
 extern crate regex;
 
-use std::collections::VecDeque;
 use std::env;
 use std::fs;
 use std::num::ParseIntError;
@@ -24,34 +24,15 @@ fn main() {
     if task == "message" {
         let sky = Sky::new(particles);
 
-        let mut t = 0;
-        let mut best_t = 0;
-        let mut best_size = sky.y_size(0);
-        let mut last_sizes: VecDeque<i64> = VecDeque::new();
-        loop {
-            // Assume that the message is when the points are
-            // most compact vertically, and that they will
-            // diverge after that
-            t += 1;
-            let size = sky.y_size(t);
-            if size < best_size {
-                best_size = size;
-                best_t = t;
-            }
-
-            // Look at the last few time slices to make
-            // sure we're really diverging and not just
-            // at a local minimum.
-            last_sizes.push_back(size);
-            while last_sizes.len() > 3 {
-                last_sizes.pop_front();
-            }
-            if last_sizes.iter().all(|sz| sz > &best_size) {
-                break;
-            }
-        }
+        // Assume that the message is when the points are
+        // most compact vertically
+        let best_t = sky.convergence_time();
         let message = sky.to_str(best_t);
         println!("{}", message);
+        match read_message(&message) {
+            Some(text) => println!("{}", text),
+            None => println!("(not a line of letters)"),
+        }
         println!("at t={}", best_t);
     } else {
         panic!("Don't know how to '{}'", task);
@@ -141,6 +122,73 @@ impl Sky {
     fn y_size(&self, t: i64) -> i64 {
         self.y_max(t) - self.y_min(t) + 1
     }
+    fn estimated_convergence(&self) -> f64 {
+        /// The time the particles are closest to their centre, by least
+        /// squares: the spread at t is the sum over the particles of
+        /// |(p - p_mean) + t(v - v_mean)|², least where its derivative is zero
+        let n = self.particles.len() as f64;
+        let mean = |f: &dyn Fn(&Particle) -> i64| {
+            self.particles.iter().map(|p| f(p) as f64).sum::<f64>() / n
+        };
+        let (x, y) = (mean(&|p| p.x), mean(&|p| p.y));
+        let (v_x, v_y) = (mean(&|p| p.v_x), mean(&|p| p.v_y));
+        let mut moving_apart = 0.0;
+        let mut speed = 0.0;
+        for p in &self.particles {
+            let (dx, dy) = (p.x as f64 - x, p.y as f64 - y);
+            let (dv_x, dv_y) = (p.v_x as f64 - v_x, p.v_y as f64 - v_y);
+            moving_apart += dx * dv_x + dy * dv_y;
+            speed += dv_x * dv_x + dv_y * dv_y;
+        }
+        if speed > 0.0 {
+            -moving_apart / speed
+        } else {
+            0.0
+        }
+    }
+    fn convergence_time(&self) -> i64 {
+        /// The earliest time from 0 on when the particles are most compact
+        /// vertically. The top is the highest of some lines and the bottom
+        /// the lowest, so y_size only falls until it first stops falling;
+        /// gallop out from the estimate to bracket that time, then bisect
+        let stops_falling = |t: i64| self.y_size(t + 1) >= self.y_size(t);
+        let start = (self.estimated_convergence().round() as i64).max(0);
+        let mut step = 1;
+        let (mut lo, mut hi) = if stops_falling(start) {
+            let mut hi = start;
+            loop {
+                if hi == 0 {
+                    return 0;
+                }
+                let lo = (start - step).max(0);
+                if !stops_falling(lo) {
+                    break (lo, hi);
+                }
+                hi = lo;
+                step *= 2;
+            }
+        } else {
+            let mut lo = start;
+            loop {
+                let hi = start + step;
+                if stops_falling(hi) {
+                    break (lo, hi);
+                }
+                lo = hi;
+                step *= 2;
+            }
+        };
+        // still falling at lo, stopped by hi
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            if stops_falling(mid) {
+                hi = mid;
+            } else {
+                lo = mid;
+            }
+        }
+        hi
+    }
     fn to_str(&self, t: i64) -> String {
         let x_size = self.x_size(t);
         let y_size = self.y_size(t);
@@ -162,6 +210,274 @@ impl Sky {
     }
 }
 
+// The puzzle's block font: letters 6 columns wide and 10 rows tall, with
+// 2 blank columns between them
+const GLYPH_WIDTH: usize = 6;
+const GLYPH_HEIGHT: usize = 10;
+const GLYPH_GAP: usize = 2;
+
+const FONT: [(char, [&str; GLYPH_HEIGHT]); 15] = [
+    (
+        'A',
+        [
+            "..##..",
+            ".#..#.",
+            "#....#",
+            "#....#",
+            "#....#",
+            "######",
+            "#....#",
+            "#....#",
+            "#....#",
+            "#....#",
+        ],
+    ),
+    (
+        'B',
+        [
+            "#####.",
+            "#....#",
+            "#....#",
+            "#....#",
+            "#####.",
+            "#....#",
+            "#....#",
+            "#....#",
+            "#....#",
+            "#####.",
+        ],
+    ),
+    (
+        'C',
+        [
+            ".####.",
+            "#....#",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#....#",
+            ".####.",
+        ],
+    ),
+    (
+        'E',
+        [
+            "######",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#####.",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "######",
+        ],
+    ),
+    (
+        'F',
+        [
+            "######",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#####.",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+        ],
+    ),
+    (
+        'G',
+        [
+            ".####.",
+            "#....#",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#..###",
+            "#....#",
+            "#....#",
+            "#...##",
+            ".###.#",
+        ],
+    ),
+    (
+        'H',
+        [
+            "#....#",
+            "#....#",
+            "#....#",
+            "#....#",
+            "######",
+            "#....#",
+            "#....#",
+            "#....#",
+            "#....#",
+            "#....#",
+        ],
+    ),
+    (
+        'J',
+        [
+            "...###",
+            "....#.",
+            "....#.",
+            "....#.",
+            "....#.",
+            "....#.",
+            "....#.",
+            "#...#.",
+            "#...#.",
+            ".###..",
+        ],
+    ),
+    (
+        'K',
+        [
+            "#....#",
+            "#...#.",
+            "#..#..",
+            "#.#...",
+            "##....",
+            "##....",
+            "#.#...",
+            "#..#..",
+            "#...#.",
+            "#....#",
+        ],
+    ),
+    (
+        'L',
+        [
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "######",
+        ],
+    ),
+    (
+        'N',
+        [
+            "#....#",
+            "##...#",
+            "##...#",
+            "#.#..#",
+            "#.#..#",
+            "#..#.#",
+            "#..#.#",
+            "#...##",
+            "#...##",
+            "#....#",
+        ],
+    ),
+    (
+        'P',
+        [
+            "#####.",
+            "#....#",
+            "#....#",
+            "#....#",
+            "#####.",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+            "#.....",
+        ],
+    ),
+    (
+        'R',
+        [
+            "#####.",
+            "#....#",
+            "#....#",
+            "#....#",
+            "#####.",
+            "#..#..",
+            "#...#.",
+            "#...#.",
+            "#....#",
+            "#....#",
+        ],
+    ),
+    (
+        'X',
+        [
+            "#....#",
+            "#....#",
+            ".#..#.",
+            ".#..#.",
+            "..##..",
+            "..##..",
+            ".#..#.",
+            ".#..#.",
+            "#....#",
+            "#....#",
+        ],
+    ),
+    (
+        'Z',
+        [
+            "######",
+            ".....#",
+            ".....#",
+            "....#.",
+            "...#..",
+            "..#...",
+            ".#....",
+            "#.....",
+            "#.....",
+            "######",
+        ],
+    ),
+];
+
+fn read_message(frame: &str) -> Option<String> {
+    /// Read the letters in a frame from Sky::to_str, with '?' for any not
+    /// in the font, or None if the frame isn't a line of letters
+    let rows: Vec<Vec<char>> = frame.lines().map(|l| l.chars().collect()).collect();
+    if rows.len() != GLYPH_HEIGHT {
+        return None;
+    }
+    let pitch = GLYPH_WIDTH + GLYPH_GAP;
+    let width = rows[0].len();
+    let lit = |x: usize| rows.iter().any(|row| row.get(x) == Some(&'#'));
+    if (0..width).any(|x| x % pitch >= GLYPH_WIDTH && lit(x)) {
+        return None;
+    }
+
+    // the last letter may end in blank columns, which get cut off
+    let mut message = String::new();
+    for left in (0..width).step_by(pitch) {
+        let glyph: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                (left..(left + GLYPH_WIDTH))
+                    .map(|x| *row.get(x).unwrap_or(&'.'))
+                    .collect()
+            })
+            .collect();
+        let letter = FONT
+            .iter()
+            .find(|&&(_, rows)| glyph.iter().zip(rows.iter()).all(|(a, b)| a == b))
+            .map_or('?', |&(letter, _)| letter);
+        message.push(letter);
+    }
+    Some(message)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -283,4 +599,95 @@ mod tests {
             assert_eq!(case.output, sky.to_str(case.t));
         }
     }
+
+    #[test]
+    fn test_read_message() {
+        // what the input reads at its closest
+        let frame = "\
+######..#####...#....#..######...####...#....#.....###.....###
+#.......#....#..#...#...#.......#....#..#...#.......#.......#.
+#.......#....#..#..#....#.......#.......#..#........#.......#.
+#.......#....#..#.#.....#.......#.......#.#.........#.......#.
+#####...#####...##......#####...#.......##..........#.......#.
+#.......#..#....##......#.......#.......##..........#.......#.
+#.......#...#...#.#.....#.......#.......#.#.........#.......#.
+#.......#...#...#..#....#.......#.......#..#....#...#...#...#.
+#.......#....#..#...#...#.......#....#..#...#...#...#...#...#.
+######..#....#..#....#..######...####...#....#...###.....###..
+";
+        assert_eq!(Some(String::from("ERKECKJJ")), read_message(frame));
+
+        // every letter, side by side
+        let letters: String = FONT.iter().map(|&(letter, _)| letter).collect();
+        let frame: String = (0..GLYPH_HEIGHT)
+            .map(|row| {
+                let glyphs: Vec<&str> = FONT.iter().map(|&(_, rows)| rows[row]).collect();
+                format!("{}\n", glyphs.join(".."))
+            })
+            .collect();
+        assert_eq!(Some(letters), read_message(&frame));
+
+        // a smudge in a letter, in the gap, or a frame the wrong height
+        let smudged = frame.replacen("######", "#####.", 1);
+        assert_eq!(Some('?'), read_message(&smudged).unwrap().chars().nth(3));
+        let in_gap = frame.replacen("#....#..", "#....##.", 1);
+        assert_eq!(None, read_message(&in_gap));
+        assert_eq!(None, read_message("#...#..###\n#...#...#.\n"));
+    }
+
+    #[test]
+    fn test_convergence_time() {
+        let example = "\
+position=< 9,  1> velocity=< 0,  2>
+position=< 7,  0> velocity=<-1,  0>
+position=< 3, -2> velocity=<-1,  1>
+position=< 6, 10> velocity=<-2, -1>
+position=< 2, -4> velocity=< 2,  2>
+position=<-6, 10> velocity=< 2, -2>
+position=< 1,  8> velocity=< 1, -1>
+position=< 1,  7> velocity=< 1,  0>
+position=<-3, 11> velocity=< 1, -2>
+position=< 7,  6> velocity=<-1, -1>
+position=<-2,  3> velocity=< 1,  0>
+position=<-4,  3> velocity=< 2,  0>
+position=<10, -3> velocity=<-1,  1>
+position=< 5, 11> velocity=< 1, -2>
+position=< 4,  7> velocity=< 0, -1>
+position=< 8, -2> velocity=< 0,  1>
+position=<15,  0> velocity=<-2,  0>
+position=< 1,  6> velocity=< 1,  0>
+position=< 8,  9> velocity=< 0, -1>
+position=< 3,  3> velocity=<-1,  1>
+position=< 0,  5> velocity=< 0, -1>
+position=<-2,  2> velocity=< 2,  0>
+position=< 5, -2> velocity=< 1,  2>
+position=< 1,  4> velocity=< 2,  1>
+position=<-2,  7> velocity=< 2, -2>
+position=< 3,  6> velocity=<-1, -1>
+position=< 5,  0> velocity=< 1,  0>
+position=<-6,  0> velocity=< 2,  0>
+position=< 5,  9> velocity=< 1, -2>
+position=<14,  7> velocity=<-2,  0>
+position=<-3,  6> velocity=< 2, -1>";
+        let particles =
+            || -> Vec<Particle> { example.lines().map(|l| l.parse().unwrap()).collect() };
+        let sky = Sky::new(particles());
+        assert_eq!(3, sky.convergence_time());
+
+        // the same message, from billions of seconds further back
+        let back = 5_000_000_000;
+        let sky = Sky::new(particles().iter().map(|p| p.at(-back)).collect());
+        assert_eq!(back + 3, sky.convergence_time());
+        assert_eq!(Sky::new(particles()).to_str(3), sky.to_str(back + 3));
+
+        // already past it, or never getting any closer together
+        let sky = Sky::new(particles().iter().map(|p| p.at(10)).collect());
+        assert_eq!(0, sky.convergence_time());
+        let drifting = vec![
+            Particle { x: 0, y: 0, v_x: 1, v_y: 1 },
+            Particle { x: 0, y: 5, v_x: -1, v_y: 1 },
+        ];
+        assert_eq!(0, Sky::new(drifting).convergence_time());
+        assert_eq!(0, Sky::new(vec![]).convergence_time());
+    }
 }
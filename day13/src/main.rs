@@ -1,3 +1,5 @@
+extern crate gridimage;
+
 use std::cmp::Ordering;
 use std::collections::HashSet;
 use std::collections::VecDeque;
@@ -5,6 +7,8 @@ use std::env;
 use std::fs::File;
 use std::io::prelude::*;
 
+use gridimage::{Animation, Frame, Palette, Rgb};
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let task = &args[1];
@@ -41,6 +45,24 @@ fn main() {
                 break;
             }
         }
+    } else if task == "gif" {
+        let out_file = args.get(3).expect("Need an output file");
+        let max_frames: usize = args
+            .get(4)
+            .map_or(500, |n| n.parse().expect("Couldn't parse frame count"));
+        let first = tracks.to_frame(&carts, FRAME_SCALE);
+        let mut animation = Animation::create(out_file, first.width(), first.height(), 50)
+            .expect("Couldn't create animation");
+        animation.push(&first).expect("Couldn't write frame");
+        for _ in 1..max_frames {
+            carts = tracks.tick_annihilate(&carts);
+            animation
+                .push(&tracks.to_frame(&carts, FRAME_SCALE))
+                .expect("Couldn't write frame");
+            if carts.len() <= 1 {
+                break;
+            }
+        }
     } else {
         panic!("Don't know how to '{}'", task);
     }
@@ -116,6 +138,19 @@ enum Track {
     N,  // nothing ' '
 }
 
+static FRAME_SCALE: usize = 3;
+static CART_COLOR: Rgb = [220, 30, 30];
+
+impl Palette for Track {
+    fn color(&self) -> Rgb {
+        match self {
+            Track::N => [15, 15, 25],
+            Track::I => [230, 230, 230],
+            _ => [130, 130, 150],
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum Direction {
     Left,
@@ -234,6 +269,14 @@ impl Tracks {
         self.grid[idx]
     }
 
+    fn to_frame(&self, carts: &[Cart], scale: usize) -> Frame {
+        let mut frame = Frame::from_cells(&self.grid, self.n_x, scale);
+        for cart in carts {
+            frame.fill_cell(cart.x, cart.y, CART_COLOR);
+        }
+        frame
+    }
+
     fn tick(&mut self, carts: &[Cart]) -> TickResult {
         let mut new_carts: Vec<Cart> = carts.to_vec();
         new_carts.sort();
@@ -1,10 +1,14 @@
 #![allow(unused_doc_comments)]
 
+extern crate gridimage;
+
 use std::env;
 use std::fmt;
 use std::fs::File;
 use std::io::prelude::*;
 
+use gridimage::{Animation, Frame, Palette, Rgb};
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let task = &args[1];
@@ -38,6 +42,19 @@ fn main() {
             hp_left,
             n_rounds * (hp_left as u32)
         );
+    } else if task == "gif" {
+        let out_file = args.get(3).expect("Need an output file");
+        let first = board.to_frame(FRAME_SCALE);
+        let mut animation = Animation::create(out_file, first.width(), first.height(), 200)
+            .expect("Couldn't create animation");
+        animation.push(&first).expect("Couldn't write frame");
+        let (n_rounds, hp_left) = board.combat_with(|b| {
+            animation
+                .push(&b.to_frame(FRAME_SCALE))
+                .expect("Couldn't write frame")
+        });
+        println!("Combat over after t={}", n_rounds);
+        println!("HP left: {}", hp_left);
     } else {
         panic!("Don't know how to '{}'", task);
     }
@@ -45,6 +62,7 @@ fn main() {
 
 static STARTING_HP: i32 = 200;
 static DEFAULT_ATTACK_POWER: i32 = 3;
+static FRAME_SCALE: usize = 8;
 
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
 enum Piece {
@@ -89,6 +107,19 @@ impl Piece {
     }
 }
 
+impl Palette for Piece {
+    fn color(&self) -> Rgb {
+        /// Creatures fade as they lose hit points
+        let fade = |hp: i32| (hp.clamp(0, STARTING_HP) * 155 / STARTING_HP + 100) as u8;
+        match *self {
+            Piece::Open => [200, 200, 190],
+            Piece::Wall => [60, 60, 60],
+            Piece::Elf(hp) => [0, fade(hp), 0],
+            Piece::Goblin(hp) => [fade(hp), 0, 0],
+        }
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Clone)]
 struct Board {
     grid: Vec<Piece>,
@@ -381,13 +412,23 @@ impl Board {
             }).sum()
     }
 
+    fn to_frame(&self, scale: usize) -> Frame {
+        Frame::from_cells(&self.grid, self.size_x, scale)
+    }
+
     fn combat(&mut self) -> (u32, i32) {
+        self.combat_with(|_| ())
+    }
+
+    fn combat_with<F: FnMut(&Board)>(&mut self, mut on_round: F) -> (u32, i32) {
+        /// Like combat, but calls back with the board after each round
         let mut t = 0;
         while !self.combat_over() {
             let completed = self.round();
             if completed {
                 t += 1;
             }
+            on_round(self);
         }
         let hp_left = self.sum_hp_left();
 
@@ -1,4 +1,5 @@
 #![allow(unused_doc_comments)]
+extern crate gridimage;
 #[macro_use]
 extern crate lazy_static;
 extern crate regex;
@@ -8,6 +9,7 @@ use std::fmt;
 use std::fs;
 use std::num::ParseIntError;
 
+use gridimage::{Animation, Frame, Palette, Rgb};
 use regex::Regex;
 
 fn main() {
@@ -25,6 +27,28 @@ fn main() {
         let source_idx = ground.xy_to_idx(500, 0);
         ground.source(source_idx);
         println!("{}", ground.count_water());
+    } else if task == "png" {
+        let out_file = args.get(3).expect("Need an output file");
+        let mut ground = read_file(filename).expect("Couldn't read input");
+        let source_idx = ground.xy_to_idx(500, 0);
+        ground.source(source_idx);
+        ground
+            .to_frame(FRAME_SCALE)
+            .save_png(out_file)
+            .expect("Couldn't write image");
+    } else if task == "gif" {
+        let out_file = args.get(3).expect("Need an output file");
+        let mut ground = read_file(filename).expect("Couldn't read input");
+        let source_idx = ground.xy_to_idx(500, 0);
+        let first = ground.to_frame(FRAME_SCALE);
+        let mut animation = Animation::create(out_file, first.width(), first.height(), 20)
+            .expect("Couldn't create animation");
+        animation.push(&first).expect("Couldn't write frame");
+        ground.source_with(source_idx, |g| {
+            animation
+                .push(&g.to_frame(FRAME_SCALE))
+                .expect("Couldn't write frame")
+        });
     } else {
         panic!("Don't know how to '{}'", task);
     }
@@ -87,6 +111,19 @@ enum Square {
     Flow,
 }
 
+static FRAME_SCALE: usize = 2;
+
+impl Palette for Square {
+    fn color(&self) -> Rgb {
+        match self {
+            Square::Sand => [237, 220, 170],
+            Square::Clay => [150, 80, 50],
+            Square::Water => [30, 80, 200],
+            Square::Flow => [120, 180, 240],
+        }
+    }
+}
+
 #[derive(Debug)]
 struct Ground {
     squares: Vec<Square>,
@@ -164,11 +201,27 @@ impl Ground {
         self.find_boundary(idx, 1)
     }
 
+    fn to_frame(&self, scale: usize) -> Frame {
+        /// Render the ground, cropped to the columns that Display shows
+        let x_lo = self.min_x.saturating_sub(1);
+        let cells: Vec<Square> = self
+            .squares
+            .chunks(self.size_x)
+            .flat_map(|row| row[x_lo..].iter().cloned())
+            .collect();
+        Frame::from_cells(&cells, self.size_x - x_lo, scale)
+    }
+
     fn source(&mut self, source_idx: usize) {
+        self.source_with(source_idx, |_| ());
+    }
+
+    fn source_with<F: FnMut(&Ground)>(&mut self, source_idx: usize, mut on_pour: F) {
+        /// Like source, but calls back with the ground after each pour
         let mut to_pour = vec![source_idx];
-        while !to_pour.is_empty() {
-            let pour_idx = to_pour.pop().unwrap();
+        while let Some(pour_idx) = to_pour.pop() {
             let spillovers = self.pour(pour_idx);
+            on_pour(self);
             to_pour.extend(spillovers);
         }
     }
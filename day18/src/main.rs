@@ -4,12 +4,16 @@ use std::env;
 use std::fmt;
 use std::fs::File;
 use std::io::Read;
+use std::path::Path;
 use std::thread;
 use std::time;
 
+extern crate gridimage;
 #[macro_use]
 extern crate itertools;
 
+use gridimage::{Animation, Frame, Palette, Rgb};
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let task = &args[1];
@@ -76,11 +80,41 @@ fn main() {
             println!("{}", board);
             thread::sleep(time::Duration::from_millis(50));
         }
+    } else if task == "frames" {
+        let out_dir = Path::new(args.get(3).expect("Need an output directory"));
+        let n_gens = n_gens_arg(&args);
+        for i in 0..=n_gens {
+            board
+                .to_frame(FRAME_SCALE)
+                .save_png(out_dir.join(format!("frame_{:04}.png", i)))
+                .expect("Couldn't write frame");
+            board = board.evolve();
+        }
+    } else if task == "gif" {
+        let out_file = args.get(3).expect("Need an output file");
+        let n_gens = n_gens_arg(&args);
+        let first = board.to_frame(FRAME_SCALE);
+        let mut animation = Animation::create(out_file, first.width(), first.height(), 50)
+            .expect("Couldn't create animation");
+        for _ in 0..=n_gens {
+            animation
+                .push(&board.to_frame(FRAME_SCALE))
+                .expect("Couldn't write frame");
+            board = board.evolve();
+        }
     } else {
         panic!("Don't know how to '{}'", task);
     }
 }
 
+static FRAME_SCALE: usize = 4;
+
+fn n_gens_arg(args: &[String]) -> usize {
+    /// Number of generations to render, from the optional 4th argument
+    args.get(4)
+        .map_or(100, |n| n.parse().expect("Couldn't parse generations"))
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum Acre {
     Open,
@@ -88,6 +122,16 @@ enum Acre {
     Yard,
 }
 
+impl Palette for Acre {
+    fn color(&self) -> Rgb {
+        match self {
+            Acre::Open => [222, 205, 160],
+            Acre::Tree => [34, 120, 45],
+            Acre::Yard => [110, 72, 40],
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 struct Board {
     acres: Vec<Acre>,
@@ -154,6 +198,10 @@ impl Board {
             size_y,
         }
     }
+    fn to_frame(&self, scale: usize) -> Frame {
+        Frame::from_cells(&self.acres, self.size_x, scale)
+    }
+
     fn idx_to_xy(&self, i: usize) -> (i32, i32) {
         ((i % self.size_x) as i32, (i / self.size_x) as i32)
     }
@@ -247,6 +295,17 @@ mod tests {
         assert_eq!(vec![4, 7, 5], board.neighbors(8));
     }
 
+    #[test]
+    fn test_to_frame() {
+        let board = read_board(".|\n#.\n");
+        let frame = board.to_frame(2);
+        assert_eq!(4, frame.width());
+        assert_eq!(4, frame.height());
+        assert_eq!(Acre::Open.color(), frame.pixel(1, 1));
+        assert_eq!(Acre::Tree.color(), frame.pixel(2, 0));
+        assert_eq!(Acre::Yard.color(), frame.pixel(0, 3));
+    }
+
     #[test]
     fn test_evolve() {
         let mut board = read_board(
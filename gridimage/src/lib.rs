@@ -0,0 +1,283 @@
+#![allow(unused_doc_comments)]
+//! Render grid simulations to PNG frames and animated GIFs
+//!
+//! Each day keeps its own cell type (`Acre`, `Square`, `Piece`, ...) and
+//! implements `Palette` for it; everything else lives here so the days
+//! don't each need to know about image encoding.
+extern crate gif;
+extern crate png;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+
+/// An RGB color
+pub type Rgb = [u8; 3];
+
+/// A cell type that knows what color to draw itself
+pub trait Palette {
+    fn color(&self) -> Rgb;
+}
+
+#[derive(Debug)]
+pub enum ImageError {
+    Io(io::Error),
+    Png(png::EncodingError),
+    Gif(gif::EncodingError),
+    TooLarge(usize, usize), // GIFs are limited to 65535 on a side
+    TooManyColors(usize),   // GIF frames can only hold 256 colors
+}
+
+impl From<io::Error> for ImageError {
+    fn from(err: io::Error) -> ImageError {
+        ImageError::Io(err)
+    }
+}
+
+impl From<png::EncodingError> for ImageError {
+    fn from(err: png::EncodingError) -> ImageError {
+        ImageError::Png(err)
+    }
+}
+
+impl From<gif::EncodingError> for ImageError {
+    fn from(err: gif::EncodingError) -> ImageError {
+        ImageError::Gif(err)
+    }
+}
+
+/// A single rendered image, stored as RGB pixels in row-major order
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Frame {
+    width: usize,
+    height: usize,
+    scale: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Frame {
+    pub fn new(size_x: usize, size_y: usize, scale: usize, background: Rgb) -> Frame {
+        /// Make a blank frame for a `size_x` by `size_y` grid,
+        /// with each cell drawn as a `scale` by `scale` block
+        assert!(scale > 0, "scale must be positive");
+        let width = size_x * scale;
+        let height = size_y * scale;
+        Frame {
+            width,
+            height,
+            scale,
+            pixels: vec![background; width * height],
+        }
+    }
+
+    pub fn from_cells<T: Palette>(cells: &[T], size_x: usize, scale: usize) -> Frame {
+        /// Render a row-major grid of cells
+        assert!(size_x > 0, "grid must have a width");
+        let size_y = cells.len().div_ceil(size_x);
+        let mut frame = Frame::new(size_x, size_y, scale, [0, 0, 0]);
+        for (i, cell) in cells.iter().enumerate() {
+            frame.fill_cell(i % size_x, i / size_x, cell.color());
+        }
+        frame
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Rgb {
+        self.pixels[x + y * self.width]
+    }
+
+    pub fn fill_cell(&mut self, x: usize, y: usize, color: Rgb) {
+        /// Color in the block for grid cell (x, y), e.g. to overlay
+        /// something (like a cart) that isn't part of the grid itself
+        for py in (y * self.scale)..((y + 1) * self.scale) {
+            let row = py * self.width;
+            for px in (x * self.scale)..((x + 1) * self.scale) {
+                self.pixels[row + px] = color;
+            }
+        }
+    }
+
+    pub fn write_png<W: Write>(&self, w: W) -> Result<(), ImageError> {
+        let mut encoder = png::Encoder::new(w, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        let data: Vec<u8> = self.pixels.iter().flat_map(|p| p.iter().cloned()).collect();
+        writer.write_image_data(&data)?;
+        Ok(())
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<(), ImageError> {
+        let file = File::create(path)?;
+        self.write_png(BufWriter::new(file))
+    }
+
+    fn indexed(&self) -> Result<(Vec<u8>, Vec<u8>), ImageError> {
+        /// Convert to palette indices plus a flat RGB palette, as GIF wants
+        /// The cell palettes are tiny, so exact colors always fit
+        let mut lookup: HashMap<Rgb, u8> = HashMap::new();
+        let mut palette: Vec<u8> = Vec::new();
+        let mut indices: Vec<u8> = Vec::with_capacity(self.pixels.len());
+        for p in &self.pixels {
+            let n_colors = lookup.len();
+            let idx = match lookup.get(p) {
+                Some(&idx) => idx,
+                None => {
+                    if n_colors >= 256 {
+                        return Err(ImageError::TooManyColors(n_colors + 1));
+                    }
+                    palette.extend_from_slice(p);
+                    lookup.insert(*p, n_colors as u8);
+                    n_colors as u8
+                }
+            };
+            indices.push(idx);
+        }
+        Ok((indices, palette))
+    }
+}
+
+/// Writes frames one at a time into a looping animated GIF
+pub struct Animation<W: Write> {
+    encoder: gif::Encoder<W>,
+    width: usize,
+    height: usize,
+    delay: u16,
+}
+
+impl<W: Write> Animation<W> {
+    pub fn new(
+        w: W,
+        width: usize,
+        height: usize,
+        delay_ms: u16,
+    ) -> Result<Animation<W>, ImageError> {
+        /// Start an animation; every frame must be `width` by `height`
+        /// GIF delays are in hundredths of a second, so `delay_ms` is rounded
+        if width > u16::MAX as usize || height > u16::MAX as usize {
+            return Err(ImageError::TooLarge(width, height));
+        }
+        let mut encoder = gif::Encoder::new(w, width as u16, height as u16, &[])?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+        Ok(Animation {
+            encoder,
+            width,
+            height,
+            delay: ((u32::from(delay_ms) + 5) / 10) as u16,
+        })
+    }
+
+    pub fn push(&mut self, frame: &Frame) -> Result<(), ImageError> {
+        assert_eq!(self.width, frame.width, "frame width changed");
+        assert_eq!(self.height, frame.height, "frame height changed");
+        let (indices, palette) = frame.indexed()?;
+        let mut gif_frame = gif::Frame::from_palette_pixels(
+            self.width as u16,
+            self.height as u16,
+            indices,
+            palette,
+            None,
+        );
+        gif_frame.delay = self.delay;
+        self.encoder.write_frame(&gif_frame)?;
+        Ok(())
+    }
+}
+
+impl Animation<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        width: usize,
+        height: usize,
+        delay_ms: u16,
+    ) -> Result<Animation<BufWriter<File>>, ImageError> {
+        let file = File::create(path)?;
+        Animation::new(BufWriter::new(file), width, height, delay_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy)]
+    enum Cell {
+        Off,
+        On,
+    }
+
+    impl Palette for Cell {
+        fn color(&self) -> Rgb {
+            match self {
+                Cell::Off => [0, 0, 0],
+                Cell::On => [255, 255, 255],
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_cells() {
+        let cells = vec![Cell::On, Cell::Off, Cell::Off, Cell::On];
+        let frame = Frame::from_cells(&cells, 2, 3);
+        assert_eq!(6, frame.width());
+        assert_eq!(6, frame.height());
+        assert_eq!([255, 255, 255], frame.pixel(0, 0));
+        assert_eq!([255, 255, 255], frame.pixel(2, 2));
+        assert_eq!([0, 0, 0], frame.pixel(3, 0));
+        assert_eq!([0, 0, 0], frame.pixel(0, 3));
+        assert_eq!([255, 255, 255], frame.pixel(5, 5));
+    }
+
+    #[test]
+    fn test_write_png() {
+        let cells = vec![Cell::On, Cell::Off];
+        let frame = Frame::from_cells(&cells, 2, 1);
+        let mut out: Vec<u8> = Vec::new();
+        frame.write_png(&mut out).unwrap();
+        assert_eq!(&[0x89, b'P', b'N', b'G'], &out[..4]);
+    }
+
+    #[test]
+    fn test_animation() {
+        let mut out: Vec<u8> = Vec::new();
+        {
+            let mut animation = Animation::new(&mut out, 2, 1, 50).unwrap();
+            animation
+                .push(&Frame::from_cells(&[Cell::On, Cell::Off], 2, 1))
+                .unwrap();
+            animation
+                .push(&Frame::from_cells(&[Cell::Off, Cell::On], 2, 1))
+                .unwrap();
+        }
+        assert_eq!(b"GIF89a", &out[..6]);
+        assert_eq!(Some(&0x3b), out.last()); // GIF trailer
+    }
+
+    #[test]
+    fn test_too_many_colors() {
+        struct Shade(u8, u8);
+        impl Palette for Shade {
+            fn color(&self) -> Rgb {
+                [self.0, self.1, 0]
+            }
+        }
+        let cells: Vec<Shade> = (0..=255u8)
+            .flat_map(|a| vec![Shade(a, 0), Shade(a, 1)])
+            .collect();
+        let frame = Frame::from_cells(&cells, 16, 1);
+        match Animation::new(Vec::new(), 16, 32, 10).unwrap().push(&frame) {
+            Err(ImageError::TooManyColors(257)) => (),
+            r => panic!("expected too many colors, got {:?}", r),
+        }
+    }
+}